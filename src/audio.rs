@@ -0,0 +1,212 @@
+use crate::{
+    loading::AudioAssets, math::inverse_lerp_clamped, pause::PauseState, piece::Carried,
+    score::GameTimer, settings::Settings, GameState,
+};
+use bevy::{
+    audio::{PitchBundle, Volume},
+    prelude::*,
+};
+use rand::Rng;
+use std::time::Duration;
+
+/// Remaining seconds of the timer from which every second ticks.
+pub const TIMER_WARNING_SECS: u32 = 10;
+/// Remaining seconds of the timer from which the music picks up.
+pub const MUSIC_RUSH_SECS: f32 = 30.;
+const MAX_MUSIC_SPEED: f32 = 1.35;
+
+/// Sound effect, played as a short tone, so there are no clips to load.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    PickUp,
+    Snap,
+    InvalidDrop,
+    Rotate,
+    /// Hex of a completed route.
+    HexPop,
+    DeadEnd,
+    TimerWarning,
+    GameOver,
+}
+
+impl Sfx {
+    fn tone(&self) -> Pitch {
+        let (frequency, duration_ms) = match self {
+            Sfx::PickUp => (660., 60),
+            Sfx::Snap => (440., 80),
+            Sfx::InvalidDrop => (160., 180),
+            Sfx::Rotate => (880., 40),
+            Sfx::HexPop => (520., 90),
+            Sfx::DeadEnd => (110., 300),
+            Sfx::TimerWarning => (990., 100),
+            Sfx::GameOver => (220., 700),
+        };
+
+        Pitch::new(frequency, Duration::from_millis(duration_ms))
+    }
+
+    /// Volume relative to the other effects.
+    fn volume(&self) -> f32 {
+        match self {
+            Sfx::Rotate | Sfx::PickUp => 0.4,
+            Sfx::HexPop | Sfx::TimerWarning => 0.6,
+            _ => 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Event, Clone, Copy, PartialEq)]
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    /// Playback speed, which raises or lowers the tone.
+    pub pitch: f32,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        Self { sfx, pitch: 1. }
+    }
+
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+}
+
+/// Time until the effect gets despawned.
+/// Without an audio device the effect never plays, so it wouldn't be despawned once it's done.
+#[derive(Component)]
+struct SfxLifetime(Timer);
+
+#[derive(Component)]
+pub struct Music;
+
+/// Music playback speed for the remaining seconds of the run timer.
+pub fn music_speed(remaining_secs: Option<f32>) -> f32 {
+    remaining_secs.map_or(1., |secs| {
+        1. + (1. - inverse_lerp_clamped(0., MUSIC_RUSH_SECS, secs)) * (MAX_MUSIC_SPEED - 1.)
+    })
+}
+
+pub struct GameAudioPlugin;
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .add_systems(OnExit(GameState::Loading), spawn_music)
+            .add_systems(OnEnter(GameState::GameOver), play_game_over)
+            .add_systems(
+                Update,
+                (
+                    (
+                        play_pick_up,
+                        warn_timer.run_if(resource_exists::<GameTimer>()),
+                    )
+                        .distributive_run_if(
+                            in_state(GameState::Game).and_then(in_state(PauseState::Running)),
+                        ),
+                    // the tones are only registered by bevy's audio plugin
+                    play_sfx.run_if(resource_exists::<Assets<Pitch>>()),
+                    despawn_finished_sfx,
+                    update_music_speed,
+                    update_music_volume.run_if(resource_changed::<Settings>()),
+                ),
+            );
+    }
+}
+
+fn spawn_music(mut cmd: Commands, audio: Res<AudioAssets>, settings: Res<Settings>) {
+    cmd.spawn((
+        AudioBundle {
+            source: audio.music.clone(),
+            settings: PlaybackSettings::LOOP
+                .with_volume(Volume::new_relative(settings.music_volume)),
+        },
+        Music,
+    ));
+}
+
+fn play_sfx(
+    mut cmd: Commands,
+    mut ev_r: EventReader<PlaySfx>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    settings: Res<Settings>,
+) {
+    for ev in ev_r.read() {
+        let mut speed = ev.pitch;
+        if ev.sfx == Sfx::HexPop {
+            // so a run of pops doesn't sound mechanical
+            speed *= rand::thread_rng().gen_range(0.97..1.03);
+        }
+
+        let tone = ev.sfx.tone();
+        let lifetime = tone.duration.div_f32(speed) + Duration::from_millis(500);
+
+        cmd.spawn((
+            PitchBundle {
+                source: pitches.add(tone),
+                settings: PlaybackSettings::DESPAWN
+                    .with_volume(Volume::new_relative(settings.sfx_volume * ev.sfx.volume()))
+                    .with_speed(speed),
+            },
+            ev.sfx,
+            SfxLifetime(Timer::new(lifetime, TimerMode::Once)),
+        ));
+    }
+}
+
+fn despawn_finished_sfx(
+    mut cmd: Commands,
+    mut sfx_q: Query<(Entity, &mut SfxLifetime)>,
+    time: Res<Time<Real>>,
+) {
+    for (e, mut lifetime) in sfx_q.iter_mut() {
+        if lifetime.0.tick(time.delta()).just_finished() {
+            cmd.entity(e).despawn();
+        }
+    }
+}
+
+fn play_pick_up(carried_q: Query<(), Added<Carried>>, mut ev_w: EventWriter<PlaySfx>) {
+    if !carried_q.is_empty() {
+        ev_w.send(PlaySfx::new(Sfx::PickUp));
+    }
+}
+
+fn warn_timer(timer: Res<GameTimer>, mut last_secs: Local<u32>, mut ev_w: EventWriter<PlaySfx>) {
+    let secs = timer.0.remaining_secs().ceil() as u32;
+
+    // ticks once the whole seconds go down, added time doesn't tick
+    if secs < *last_secs && secs > 0 && secs <= TIMER_WARNING_SECS {
+        ev_w.send(PlaySfx::new(Sfx::TimerWarning));
+    }
+
+    *last_secs = secs;
+}
+
+fn play_game_over(mut ev_w: EventWriter<PlaySfx>) {
+    ev_w.send(PlaySfx::new(Sfx::GameOver));
+}
+
+fn update_music_speed(
+    timer: Option<Res<GameTimer>>,
+    state: Res<State<GameState>>,
+    music_q: Query<&AudioSink, With<Music>>,
+) {
+    let remaining = timer
+        .filter(|_| *state.get() == GameState::Game)
+        .map(|timer| timer.0.remaining_secs());
+    let speed = music_speed(remaining);
+
+    // there are no sinks without an audio device
+    for sink in music_q.iter() {
+        if sink.speed() != speed {
+            sink.set_speed(speed);
+        }
+    }
+}
+
+fn update_music_volume(settings: Res<Settings>, music_q: Query<&AudioSink, With<Music>>) {
+    for sink in music_q.iter() {
+        sink.set_volume(settings.master_volume * settings.music_volume);
+    }
+}
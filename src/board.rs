@@ -0,0 +1,128 @@
+use crate::{difficulty::DifficultyTier, piece::PieceHexSpec};
+use bevy::{prelude::*, utils::HashSet};
+use hexx::{shapes, Hex};
+use rand::Rng;
+
+/// Hexes of a generated board, without any entities attached.
+/// Turned into entities and a [`crate::map::WorldMap`] by [`crate::map::spawn_board_layout`].
+#[derive(Debug, Clone, Default, Resource)]
+pub struct BoardLayout {
+    pub map_radius: u32,
+    /// Empty hexes pieces can be placed on (including the padding around houses).
+    /// The hexes inside the map radius go first, followed by the padding.
+    pub playable: Vec<Hex>,
+    /// Houses in the order they were placed in.
+    pub houses: Vec<Hex>,
+    /// Occupied hexes of the mid island.
+    pub blocked: Vec<Hex>,
+    /// Route hexes placed from the start, their offsets being (playable) board hexes.
+    pub routes: Vec<PieceHexSpec>,
+}
+
+impl BoardLayout {
+    pub fn is_padding(&self, hex: Hex) -> bool {
+        hex.ulength() > self.map_radius
+    }
+
+    /// All hexes that are part of the board.
+    pub fn hexes(&self) -> impl Iterator<Item = Hex> + '_ {
+        self.playable
+            .iter()
+            .chain(self.houses.iter())
+            .chain(self.blocked.iter())
+            .copied()
+    }
+}
+
+/// Generates a board of the given difficulty tier.
+/// The same rng state always yields the same layout.
+pub fn generate_board(tier: &DifficultyTier, rng: &mut impl Rng) -> BoardLayout {
+    let map_radius = tier.map_radius;
+    let direction_group = tier.houses.choose(rng);
+
+    let mut playable: Vec<_> = shapes::hexagon(Hex::ZERO, map_radius).collect();
+    let mut board_hexes: HashSet<_> = playable.iter().copied().collect();
+
+    // houses
+    let mut houses = Vec::with_capacity(direction_group.len());
+    let mut wedge_indices = HashSet::with_capacity(direction_group.len());
+    let allow_houses_outside_grid = tier.houses_outside_grid;
+
+    for dir in direction_group.iter() {
+        'wedge: loop {
+            for (i, hex) in Hex::ZERO
+                .corner_wedge(
+                    ((map_radius - 2.min(map_radius))
+                        ..=(map_radius + if allow_houses_outside_grid { 1 } else { 0 }))
+                        .rev(),
+                    *dir,
+                )
+                .enumerate()
+            {
+                if houses.contains(&hex) || wedge_indices.contains(&i) {
+                    continue;
+                }
+
+                if rng.gen_bool(0.25) {
+                    board_hexes.insert(hex);
+                    houses.push(hex);
+                    wedge_indices.insert(i);
+
+                    let mut neighbours = hex.all_neighbors();
+
+                    if rng.gen_bool(0.5) {
+                        neighbours.reverse();
+                    }
+
+                    for neighbour in neighbours.iter() {
+                        if board_hexes.contains(neighbour) {
+                            continue;
+                        } else if rng.gen_bool(0.25) {
+                            break;
+                        }
+
+                        board_hexes.insert(*neighbour);
+                        playable.push(*neighbour);
+                    }
+
+                    break 'wedge;
+                }
+            }
+        }
+    }
+
+    // mid island
+    let mut blocked = Vec::new();
+
+    if let Some(island_range) = tier.island_range() {
+        let mut skip_count = 0;
+        for island_hex in Hex::ZERO.spiral_range(island_range.clone()) {
+            if skip_count > 0 {
+                skip_count -= 1;
+                continue;
+            } else if rng.gen_bool(if island_range.end() == &1 {
+                0.225
+            } else {
+                0.125
+            }) {
+                skip_count = 2;
+                continue;
+            }
+
+            if !houses.contains(&island_hex) {
+                blocked.push(island_hex);
+            }
+        }
+    }
+
+    let occupied: HashSet<_> = houses.iter().chain(blocked.iter()).copied().collect();
+    playable.retain(|hex| !occupied.contains(hex));
+
+    BoardLayout {
+        map_radius,
+        playable,
+        houses,
+        blocked,
+        routes: Vec::new(),
+    }
+}
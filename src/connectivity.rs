@@ -0,0 +1,129 @@
+use crate::{animation::get_spritesheet_color_anim, map::WorldMap, settings::Settings, GameState};
+use bevy::{
+    prelude::*,
+    utils::{petgraph::stable_graph::NodeIndex, HashMap},
+};
+use bevy_tweening::EaseFunction;
+use hexx::Hex;
+
+/// Union-find over the map graph nodes, so linked houses can be looked up without walking the graph.
+/// Sets can only be merged, so removing hexes rebuilds them.
+#[derive(Debug, Default, Clone)]
+pub struct NodeSets {
+    parents: HashMap<NodeIndex, NodeIndex>,
+    sizes: HashMap<NodeIndex, usize>,
+}
+
+impl NodeSets {
+    pub fn find(&self, mut node: NodeIndex) -> NodeIndex {
+        while let Some(parent) = self.parents.get(&node) {
+            node = *parent;
+        }
+
+        node
+    }
+
+    /// Merges the sets of `a` and `b`, returns false if they already were in the same one.
+    pub fn union(&mut self, a: NodeIndex, b: NodeIndex) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+
+        if root_a == root_b {
+            return false;
+        }
+
+        let size_a = self.sizes.get(&root_a).copied().unwrap_or(1);
+        let size_b = self.sizes.get(&root_b).copied().unwrap_or(1);
+        let (root, child) = if size_a >= size_b {
+            (root_a, root_b)
+        } else {
+            (root_b, root_a)
+        };
+
+        // attaching the smaller set keeps the trees shallow without path compression
+        self.parents.insert(child, root);
+        self.sizes.remove(&child);
+        self.sizes.insert(root, size_a + size_b);
+
+        true
+    }
+
+    pub fn equiv(&self, a: NodeIndex, b: NodeIndex) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+/// Houses grouped by the route network linking them, updated after every placement.
+#[derive(Debug, Resource, Default)]
+pub struct HouseConnectivity {
+    /// Houses without any linked house get a group of their own.
+    pub groups: Vec<Vec<Hex>>,
+}
+
+impl HouseConnectivity {
+    pub fn house_count(&self) -> usize {
+        self.groups.iter().map(|group| group.len()).sum()
+    }
+
+    /// Houses linked to at least one other house.
+    pub fn connected_count(&self) -> usize {
+        self.groups
+            .iter()
+            .filter(|group| group.len() > 1)
+            .map(|group| group.len())
+            .sum()
+    }
+}
+
+pub struct ConnectivityPlugin;
+impl Plugin for ConnectivityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HouseConnectivity>().add_systems(
+            Update,
+            (
+                update_connectivity.run_if(resource_exists_and_changed::<WorldMap>()),
+                tint_houses.after(update_connectivity).run_if(
+                    resource_changed::<HouseConnectivity>().or_else(resource_changed::<Settings>()),
+                ),
+            )
+                .distributive_run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+fn update_connectivity(map: Res<WorldMap>, mut connectivity: ResMut<HouseConnectivity>) {
+    let groups = map.house_groups();
+
+    if groups != connectivity.groups {
+        connectivity.groups = groups;
+    }
+}
+
+fn tint_houses(
+    mut cmd: Commands,
+    map: Res<WorldMap>,
+    connectivity: Res<HouseConnectivity>,
+    settings: Res<Settings>,
+) {
+    let groups = &settings.palette().groups;
+    let mut color_i = 0;
+
+    for group in connectivity.groups.iter() {
+        let color = if group.len() > 1 {
+            color_i += 1;
+            groups[(color_i - 1) % groups.len()]
+        } else {
+            Color::WHITE
+        };
+
+        for house in group.iter() {
+            if let Some(house_e) = map.hexes.get(house).and_then(|h| h.placed_hex_e) {
+                cmd.entity(house_e).try_insert(get_spritesheet_color_anim(
+                    None,
+                    color,
+                    300,
+                    EaseFunction::QuadraticOut,
+                ));
+            }
+        }
+    }
+}
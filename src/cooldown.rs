@@ -18,11 +18,18 @@ impl<T: Send + Sync> Cooldown<T> {
 }
 
 pub struct Rotating;
+pub struct CursorMoving;
 
 pub struct CooldownPlugin;
 impl Plugin for CooldownPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, process_cooldown::<Rotating>);
+        app.add_systems(
+            Update,
+            (
+                process_cooldown::<Rotating>,
+                process_cooldown::<CursorMoving>,
+            ),
+        );
     }
 }
 
@@ -0,0 +1,331 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    board::BoardLayout,
+    game_mode::GameMode,
+    high_scores::{format_date, now_secs, spawn_text},
+    level::TestLevel,
+    loading::FontAssets,
+    map::WorldMap,
+    menu::{ButtonColors, RunSystem},
+    pause::AbandonedRun,
+    replay::ReplayPlayback,
+    reset::{RegisteredSystems, Resettable},
+    score::{Level, Score},
+    storage::{KeyValueStore, Storage},
+    GameState,
+};
+use bevy::{prelude::*, utils::HashSet};
+use bevy_tweening::{Animator, EaseFunction};
+use hexx::Hex;
+use serde::{Deserialize, Serialize};
+
+const DAILY_KEY: &str = "daily.ron";
+
+/// Days since the Unix epoch (UTC).
+pub fn today() -> u64 {
+    now_secs() / 86_400
+}
+
+/// Run seed of the daily challenge of `day`, the same for everyone playing that day.
+pub fn daily_seed(day: u64) -> u64 {
+    // splitmix64, so consecutive days don't get similar seeds
+    let mut seed = day.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    seed ^ (seed >> 31)
+}
+
+/// Result of the scored attempt of a daily challenge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub day: u64,
+    pub score: u32,
+    pub hives: u32,
+    /// Text to share the result with, the summary followed by the grid of the final hive.
+    pub share: String,
+}
+
+/// Result of the last scored daily challenge, only one attempt a day is scored.
+#[derive(Debug, Resource, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyResults {
+    pub last: Option<DailyResult>,
+}
+
+impl DailyResults {
+    pub fn load(store: &dyn KeyValueStore) -> Self {
+        store
+            .load(DAILY_KEY)
+            .and_then(|data| match ron::from_str(&data) {
+                Ok(results) => Some(results),
+                Err(e) => {
+                    warn!("Failed to parse daily results: {e}");
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, store: &mut dyn KeyValueStore) {
+        match ron::to_string(self) {
+            Ok(data) => store.save(DAILY_KEY, &data),
+            Err(e) => warn!("Failed to serialize daily results: {e}"),
+        }
+    }
+
+    pub fn played(&self, day: u64) -> bool {
+        self.last.as_ref().map_or(false, |result| result.day == day)
+    }
+}
+
+/// Daily challenge that's being played.
+#[derive(Debug, Resource, Clone, Copy)]
+pub struct DailyRun {
+    pub day: u64,
+    /// Whether it's the first attempt of the day, the later ones are just practice.
+    pub scored: bool,
+}
+
+/// Shareable summary of a daily challenge.
+pub fn share_text(day: u64, score: u32, hives: u32) -> String {
+    format!(
+        "Bee Trails Daily {}\nScore {score} | Hives {hives}",
+        format_date(day)
+    )
+}
+
+/// Emoji grid of the hive, a row of text per row of hexes.
+pub fn hive_grid(map: &WorldMap, board: &BoardLayout) -> String {
+    let houses: HashSet<_> = map.houses().collect();
+    let routes: HashSet<_> = map.route_hexes().collect();
+    // houses can be outside of the map radius
+    let radius = board.hexes().map(|hex| hex.ulength()).max().unwrap_or(0) as i32;
+    let mut rows = Vec::with_capacity(radius as usize * 2 + 1);
+
+    for y in -radius..=radius {
+        // indented by half of the hexes the row is missing
+        let mut row = " ".repeat(y.unsigned_abs() as usize);
+
+        for x in (-radius).max(-y - radius)..=radius.min(-y + radius) {
+            let hex = Hex::new(x, y);
+            row.push(if !map.hexes.contains_key(&hex) {
+                // full-width, so the rows stay aligned
+                '\u{3000}'
+            } else if houses.contains(&hex) {
+                '🏠'
+            } else if board.blocked.contains(&hex) {
+                '⬛'
+            } else if routes.contains(&hex) {
+                '🟨'
+            } else if map
+                .hexes
+                .get(&hex)
+                .map_or(false, |map_hex| map_hex.placed_hex_e.is_some())
+            {
+                '🟩'
+            } else {
+                '⬜'
+            });
+        }
+
+        rows.push(row);
+    }
+
+    rows.join("\n")
+}
+
+pub struct DailyPlugin;
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DailyResults>()
+            .add_systems(Startup, load_daily_results)
+            .add_systems(OnEnter(GameState::Game), start_daily)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (finish_daily, setup_game_over_ui).chain(),
+            );
+    }
+}
+
+fn load_daily_results(mut results: ResMut<DailyResults>, storage: Res<Storage>) {
+    *results = DailyResults::load(storage.0.as_ref());
+}
+
+fn start_daily(
+    mut cmd: Commands,
+    mode: Res<GameMode>,
+    results: Res<DailyResults>,
+    playback: Option<Res<ReplayPlayback>>,
+    test_level: Option<Res<TestLevel>>,
+) {
+    if *mode != GameMode::Daily {
+        cmd.remove_resource::<DailyRun>();
+        return;
+    }
+
+    let day = today();
+    cmd.insert_resource(DailyRun {
+        day,
+        // replays and test plays of edited levels aren't attempts
+        scored: !results.played(day) && playback.is_none() && test_level.is_none(),
+    });
+}
+
+fn finish_daily(
+    daily: Option<Res<DailyRun>>,
+    score: Res<Score>,
+    level: Res<Level>,
+    map: Option<Res<WorldMap>>,
+    board: Option<Res<BoardLayout>>,
+    abandoned: Option<Res<AbandonedRun>>,
+    mut results: ResMut<DailyResults>,
+    mut storage: ResMut<Storage>,
+) {
+    let Some(daily) = daily.filter(|daily| daily.scored && abandoned.is_none()) else {
+        return;
+    };
+
+    let mut share = share_text(daily.day, score.0, level.0);
+    if let (Some(map), Some(board)) = (map, board) {
+        share.push('\n');
+        share.push_str(&hive_grid(&map, &board));
+    }
+
+    results.last = Some(DailyResult {
+        day: daily.day,
+        score: score.0,
+        hives: level.0,
+        share,
+    });
+    results.save(storage.0.as_mut());
+}
+
+#[derive(Component)]
+struct CopyResultText;
+
+/// Copies the share text of today's daily challenge to the clipboard.
+pub fn copy_daily_result(
+    results: Res<DailyResults>,
+    mut text_q: Query<&mut Text, With<CopyResultText>>,
+) {
+    let Some(result) = results.last.as_ref().filter(|result| result.day == today()) else {
+        return;
+    };
+
+    let label = match copy_to_clipboard(&result.share) {
+        Ok(()) => "COPIED",
+        Err(e) => {
+            warn!("Failed to copy the daily result: {e}");
+            "COPY FAILED"
+        }
+    };
+
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = label.to_string();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    // the returned promise is left alone, the browser reports permission errors by itself
+    let _ = web_sys::window()
+        .ok_or("No window")?
+        .navigator()
+        .clipboard()
+        .write_text(text);
+
+    Ok(())
+}
+
+fn setup_game_over_ui(
+    mut cmd: Commands,
+    daily: Option<Res<DailyRun>>,
+    results: Res<DailyResults>,
+    systems: Res<RegisteredSystems>,
+    fonts: Res<FontAssets>,
+) {
+    let Some(daily) = daily else {
+        return;
+    };
+
+    let text_style = TextStyle {
+        font_size: 30.,
+        color: Color::rgb_u8(61, 51, 51),
+        font: fonts.main.clone(),
+    };
+
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                top: Val::Px(30.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        Resettable,
+    ))
+    .with_children(|b| {
+        spawn_text(
+            b,
+            format!("DAILY {}", format_date(daily.day)),
+            text_style.clone(),
+            1000,
+        );
+
+        if !daily.scored {
+            spawn_text(
+                b,
+                "PRACTICE - TODAY'S ATTEMPT IS ALREADY SCORED".to_string(),
+                TextStyle {
+                    font_size: 20.,
+                    ..text_style.clone()
+                },
+                1100,
+            );
+        }
+
+        if !results.played(daily.day) {
+            return;
+        }
+
+        let button_colors = ButtonColors::default();
+        b.spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(220.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::top(Val::Px(10.)),
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                ..default()
+            },
+            button_colors,
+            RunSystem(systems.copy_daily_result),
+            Animator::new(delay_tween(
+                get_scale_tween(None, Vec3::ONE, 350, EaseFunction::BackOut),
+                1200,
+            )),
+            Resettable,
+        ))
+        .with_children(|b| {
+            b.spawn((
+                TextBundle::from_section("COPY RESULT", text_style),
+                CopyResultText,
+            ));
+        });
+    });
+}
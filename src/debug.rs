@@ -1,9 +1,15 @@
 use crate::{
     ecs::DelayedEvent,
     input::GameAction,
+    level_editor::LevelEditorPlugin,
     loading::MainCam,
+    map::{BoardSeed, WorldMap},
+    pause::PauseState,
+    piece::HexBlueprints,
     reset::RegisteredSystems,
     score::{Level, UpdateTimerEv},
+    settings::Settings,
+    solver::empty_hexes_connect_houses,
     GameState,
 };
 use bevy::{prelude::*, window::PrimaryWindow};
@@ -15,6 +21,8 @@ use leafwing_input_manager::prelude::*;
 pub enum DebugAction {
     Reset,
     RaiseLevel,
+    CheckSolvable,
+    ToggleEditor,
 }
 
 pub struct DebugPlugin;
@@ -24,12 +32,21 @@ impl Plugin for DebugPlugin {
             .init_resource::<ActionState<DebugAction>>()
             .insert_resource(
                 InputMap::default()
-                    .insert(KeyCode::Escape, DebugAction::Reset)
                     .insert(KeyCode::R, DebugAction::Reset)
                     .insert(KeyCode::NumpadAdd, DebugAction::RaiseLevel)
+                    .insert(KeyCode::C, DebugAction::CheckSolvable)
+                    .insert(KeyCode::F2, DebugAction::ToggleEditor)
                     .build(),
             )
-            .add_systems(Update, handle_input.run_if(in_state(GameState::Game)));
+            .add_plugins(LevelEditorPlugin)
+            .add_systems(
+                Update,
+                (
+                    handle_input
+                        .run_if(in_state(GameState::Game).and_then(in_state(PauseState::Running))),
+                    toggle_editor.run_if(not(in_state(GameState::Loading))),
+                ),
+            );
 
         if cfg!(not(target_arch = "wasm32")) {
             app.add_plugins(EditorPlugin::default());
@@ -43,16 +60,45 @@ fn handle_input(
     systems: Res<RegisteredSystems>,
     mut ev_w: EventWriter<UpdateTimerEv>,
     mut lvl: ResMut<Level>,
+    mut seed: ResMut<BoardSeed>,
+    map: Option<Res<WorldMap>>,
+    blueprints: Res<HexBlueprints>,
+    settings: Res<Settings>,
 ) {
     if input.just_pressed(DebugAction::Reset) {
+        seed.reroll += 1;
         cmd.run_system(systems.reset);
-        cmd.add_trauma(0.7);
+        cmd.add_trauma(settings.trauma(0.7));
         ev_w.send(UpdateTimerEv(-5.));
     }
 
     if input.just_pressed(DebugAction::RaiseLevel) {
         lvl.0 += 1;
+        seed.reroll = 0;
         cmd.run_system(systems.reset);
         ev_w.send(UpdateTimerEv(30.));
     }
+
+    if input.just_pressed(DebugAction::CheckSolvable) {
+        if let Some(map) = map {
+            info!(
+                "Houses connectable through the empty hexes: {}",
+                empty_hexes_connect_houses(&map, &blueprints)
+            );
+        }
+    }
+}
+
+fn toggle_editor(
+    input: Res<ActionState<DebugAction>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(DebugAction::ToggleEditor) {
+        next_state.set(if *state.get() == GameState::Editor {
+            GameState::Tutorial
+        } else {
+            GameState::Editor
+        });
+    }
 }
@@ -0,0 +1,221 @@
+use crate::{loading::ConfigAssets, piece::HexBlueprints};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use hexx::Direction;
+use rand::{distributions::WeightedError, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::RangeInclusive};
+
+/// Route hex blueprint offered in lots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlueprintConfig {
+    /// Connected sides going clockwise from the top-right edge (pointy hexes).
+    pub connected_sides: [bool; 6],
+    pub atlas_index: usize,
+    /// `(from_level, weight)` pairs ordered by the level.
+    /// The blueprint isn't offered before the first level.
+    pub weights: Vec<(u32, u8)>,
+}
+
+impl BlueprintConfig {
+    pub fn weight(&self, level: u32) -> u8 {
+        self.weights
+            .iter()
+            .rev()
+            .find(|(from_level, _)| *from_level <= level)
+            .map_or(0, |(_, weight)| *weight)
+    }
+}
+
+/// Directions of the grid wedges houses get placed in, one house per direction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HouseDirections {
+    /// One of the groups picked at random.
+    OneOf(Vec<Vec<Direction>>),
+    /// Sets of distinct random directions with the given sizes, the sets may overlap.
+    Random(Vec<usize>),
+}
+
+impl HouseDirections {
+    pub fn choose(&self, rng: &mut impl Rng) -> Vec<Direction> {
+        match self {
+            HouseDirections::OneOf(groups) => groups.choose(rng).cloned().unwrap_or_default(),
+            HouseDirections::Random(counts) => {
+                let mut dirs = Vec::with_capacity(counts.iter().sum());
+
+                for count in counts.iter() {
+                    dirs.extend(Direction::ALL_DIRECTIONS.choose_multiple(rng, *count));
+                }
+
+                dirs
+            }
+        }
+    }
+}
+
+/// Board and piece settings of the levels starting at `from_level`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DifficultyTier {
+    pub from_level: u32,
+    pub map_radius: u32,
+    /// First and last ring of the mid island, no island is placed when missing.
+    pub island: Option<(u32, u32)>,
+    /// Whether houses can be placed a ring outside of the grid.
+    pub houses_outside_grid: bool,
+    pub houses: HouseDirections,
+    /// Weights of the piece sizes, starting with a single hex piece.
+    pub piece_size_weights: Vec<u8>,
+}
+
+impl DifficultyTier {
+    pub fn island_range(&self) -> Option<RangeInclusive<u32>> {
+        self.island.map(|(start, end)| start..=end)
+    }
+}
+
+/// Difficulty curve of the generated levels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Asset, TypePath)]
+pub struct DifficultyConfig {
+    /// Indexed by level files and scripted lots, so new blueprints should go last.
+    pub blueprints: Vec<BlueprintConfig>,
+    /// Ordered by the level they start at.
+    pub tiers: Vec<DifficultyTier>,
+}
+
+impl DifficultyConfig {
+    /// Parses the config and checks the blueprints can be built from it.
+    pub fn from_ron(data: &str) -> Result<Self, DifficultyError> {
+        let config: Self = ron::from_str(data)?;
+        HexBlueprints::new(&config)?;
+
+        Ok(config)
+    }
+
+    /// Tier of the level, levels before the first tier use the first one.
+    pub fn tier(&self, level: u32) -> &DifficultyTier {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.from_level <= level)
+            .unwrap_or(&self.tiers[0])
+    }
+}
+
+/// Difficulty config in use, replaced whenever the config asset gets (re)loaded.
+#[derive(Debug, Resource, Deref)]
+pub struct Difficulty(pub DifficultyConfig);
+
+#[derive(Debug)]
+pub enum DifficultyError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    NoTiers,
+    PieceSize { level: u32, size: usize },
+    Weights { level: u32, error: WeightedError },
+}
+
+impl fmt::Display for DifficultyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DifficultyError::Io(e) => write!(f, "Failed to read the difficulty config: {e}"),
+            DifficultyError::Ron(e) => write!(f, "Failed to parse the difficulty config: {e}"),
+            DifficultyError::NoTiers => write!(f, "The difficulty config has no tiers"),
+            DifficultyError::PieceSize { level, size } => {
+                write!(f, "Pieces of level {level} can't have {size} hexes")
+            }
+            DifficultyError::Weights { level, error } => {
+                write!(f, "Invalid weights of level {level}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DifficultyError {}
+
+impl From<std::io::Error> for DifficultyError {
+    fn from(e: std::io::Error) -> Self {
+        DifficultyError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for DifficultyError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        DifficultyError::Ron(e)
+    }
+}
+
+#[derive(Default)]
+pub struct DifficultyLoader;
+
+impl AssetLoader for DifficultyLoader {
+    type Asset = DifficultyConfig;
+    type Settings = ();
+    type Error = DifficultyError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<DifficultyConfig, DifficultyError>> {
+        Box::pin(async move {
+            let mut data = String::new();
+            reader.read_to_string(&mut data).await?;
+
+            // an invalid config fails to load, so a hot reload keeps the previous one
+            DifficultyConfig::from_ron(&data)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["difficulty.ron"]
+    }
+}
+
+pub struct DifficultyPlugin;
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DifficultyConfig>()
+            .init_asset_loader::<DifficultyLoader>()
+            // before the state transitions, so the config is in place when the game starts
+            .add_systems(
+                PreUpdate,
+                apply_difficulty.run_if(resource_exists::<ConfigAssets>()),
+            );
+    }
+}
+
+fn apply_difficulty(
+    mut cmd: Commands,
+    config_assets: Res<ConfigAssets>,
+    configs: Res<Assets<DifficultyConfig>>,
+    difficulty: Option<Res<Difficulty>>,
+    mut asset_ev_r: EventReader<AssetEvent<DifficultyConfig>>,
+) {
+    let modified = asset_ev_r
+        .read()
+        .any(|ev| ev.is_modified(&config_assets.difficulty));
+
+    if difficulty.is_some() && !modified {
+        return;
+    }
+
+    let Some(config) = configs.get(&config_assets.difficulty) else {
+        return;
+    };
+
+    match HexBlueprints::new(config) {
+        Ok(blueprints) => {
+            if modified {
+                info!("Difficulty config reloaded, it applies from the next board");
+            }
+
+            cmd.insert_resource(blueprints);
+            cmd.insert_resource(Difficulty(config.clone()));
+        }
+        Err(e) => warn!("{e}"),
+    }
+}
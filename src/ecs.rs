@@ -1,4 +1,7 @@
-use crate::score::{UpdateScoreEv, UpdateTimerEv};
+use crate::{
+    audio::PlaySfx,
+    score::{UpdateScoreEv, UpdateTimerEv},
+};
 use bevy::{ecs::system::SystemId, prelude::*};
 use std::time::Duration;
 
@@ -11,11 +14,20 @@ impl Plugin for EcsPlugin {
                 run_delayed_systems,
                 send_delayed_events::<UpdateScoreEv>,
                 send_delayed_events::<UpdateTimerEv>,
+                send_delayed_events::<PlaySfx>,
             ),
         );
     }
 }
 
+/// Filter of the pending delayed systems and events.
+pub type Delayed = Or<(
+    With<DelayedSystem>,
+    With<DelayedEvent<UpdateScoreEv>>,
+    With<DelayedEvent<UpdateTimerEv>>,
+    With<DelayedEvent<PlaySfx>>,
+)>;
+
 #[derive(Component)]
 pub struct DelayedSystem {
     pub system_id: SystemId,
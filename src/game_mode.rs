@@ -0,0 +1,175 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    loading::LevelAssets,
+    map_completion::CompletedMap,
+    menu::{ButtonColors, RunSystem},
+    reset::Resettable,
+    score::Score,
+    GameState,
+};
+use bevy::{ecs::system::SystemId, prelude::*, time::Stopwatch};
+use bevy_tweening::{Animator, EaseFunction};
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Number of hives a marathon run takes.
+pub const MARATHON_HIVES: u32 = 5;
+/// Marathon runs score the seconds left of this.
+pub const MARATHON_PAR_SECS: f32 = 600.;
+
+#[derive(
+    Debug, Resource, Default, Clone, Copy, PartialEq, Eq, EnumIter, Serialize, Deserialize,
+)]
+pub enum GameMode {
+    /// Race the clock, completed hives add time.
+    #[default]
+    Timed,
+    /// No timer and no game over.
+    Zen,
+    /// A fixed number of hives scored by the total time.
+    Marathon,
+    /// Hand-authored boards with a fixed piece sequence.
+    Puzzle,
+    /// A timed run on boards seeded by the date, so everyone gets the same ones.
+    /// Only the first attempt of the day is scored.
+    Daily,
+}
+
+impl GameMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Timed => "TIMED",
+            GameMode::Zen => "ZEN",
+            GameMode::Marathon => "MARATHON",
+            GameMode::Puzzle => "PUZZLE",
+            GameMode::Daily => "DAILY",
+        }
+    }
+
+    /// Whether the run is raced against [`GameTimer`](crate::score::GameTimer).
+    pub fn is_timed(&self) -> bool {
+        matches!(self, GameMode::Timed | GameMode::Daily)
+    }
+
+    pub fn next(&self) -> Self {
+        GameMode::iter()
+            .cycle()
+            .skip_while(|mode| mode != self)
+            .nth(1)
+            .unwrap()
+    }
+
+    /// Number of hives that end the run, `None` if only the timer (or nothing) ends it.
+    pub fn hive_count(&self, levels: &LevelAssets) -> Option<u32> {
+        match self {
+            GameMode::Timed | GameMode::Zen | GameMode::Daily => None,
+            GameMode::Marathon => Some(MARATHON_HIVES),
+            GameMode::Puzzle => Some(levels.puzzles.len() as u32),
+        }
+    }
+}
+
+/// Time spent building hives this run (completion animations aren't counted).
+#[derive(Debug, Resource, Default, Deref, DerefMut)]
+pub struct RunClock(pub Stopwatch);
+
+pub struct GameModePlugin;
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<RunClock>()
+            .add_systems(OnEnter(GameState::Game), restart_clock)
+            .add_systems(
+                Update,
+                (
+                    tick_clock.run_if(
+                        in_state(GameState::Game).and_then(not(resource_exists::<CompletedMap>())),
+                    ),
+                    update_mode_text.run_if(resource_changed::<GameMode>()),
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+struct ModeText;
+
+fn restart_clock(mut clock: ResMut<RunClock>) {
+    clock.reset();
+}
+
+fn tick_clock(mut clock: ResMut<RunClock>, time: Res<Time>) {
+    clock.tick(time.delta());
+}
+
+pub fn cycle_mode(mut mode: ResMut<GameMode>) {
+    *mode = mode.next();
+}
+
+/// Ends the run once its last hive has been completed.
+pub fn end_run(
+    mode: Res<GameMode>,
+    clock: Res<RunClock>,
+    mut score: ResMut<Score>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if *mode == GameMode::Marathon {
+        score.0 = (MARATHON_PAR_SECS - clock.elapsed_secs()).max(0.) as u32;
+    }
+
+    next_state.set(GameState::GameOver);
+}
+
+pub fn spawn_mode_btn(
+    children: &mut ChildBuilder,
+    tween_delay_ms: u64,
+    font: Handle<Font>,
+    mode: GameMode,
+    cycle_mode: SystemId,
+) -> Entity {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(220.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..Default::default()
+                },
+                background_color: button_colors.normal.into(),
+                transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                ..Default::default()
+            },
+            button_colors,
+            RunSystem(cycle_mode),
+            Animator::new(delay_tween(
+                get_scale_tween(None, Vec3::ONE, 350, EaseFunction::BackOut),
+                tween_delay_ms,
+            )),
+            Resettable,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    format!("MODE: {}", mode.label()),
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::rgb_u8(61, 51, 51),
+                        font,
+                        ..default()
+                    },
+                ),
+                ModeText,
+            ));
+        })
+        .id()
+}
+
+fn update_mode_text(mode: Res<GameMode>, mut text_q: Query<&mut Text, With<ModeText>>) {
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = format!("MODE: {}", mode.label());
+    }
+}
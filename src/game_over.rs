@@ -1,8 +1,10 @@
 use crate::{
     animation::{delay_tween, get_scale_tween},
+    game_mode::{GameMode, RunClock},
     loading::FontAssets,
     menu::spawn_play_btn,
-    reset::{tween_reset, Resettable},
+    replay::{spawn_replay_btn, LastReplay},
+    reset::{tween_reset, RegisteredSystems, Resettable},
     score::Score,
     GameState,
 };
@@ -18,7 +20,15 @@ impl Plugin for GameOverPlugin {
     }
 }
 
-fn setup_ui(mut cmd: Commands, score: Res<Score>, fonts: Res<FontAssets>) {
+fn setup_ui(
+    mut cmd: Commands,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    clock: Res<RunClock>,
+    last_replay: Res<LastReplay>,
+    systems: Res<RegisteredSystems>,
+    fonts: Res<FontAssets>,
+) {
     cmd.spawn((NodeBundle {
         style: Style {
             flex_direction: FlexDirection::Column,
@@ -60,7 +70,11 @@ fn setup_ui(mut cmd: Commands, score: Res<Score>, fonts: Res<FontAssets>) {
             b.spawn((
                 TextBundle {
                     text: Text::from_section(
-                        format!("SCORE: {}", score.0),
+                        if *mode == GameMode::Marathon {
+                            format!("TIME: {:.1}", clock.elapsed_secs())
+                        } else {
+                            format!("SCORE: {}", score.0)
+                        },
                         TextStyle {
                             font_size: 90.,
                             font: fonts.main.clone(),
@@ -84,5 +98,9 @@ fn setup_ui(mut cmd: Commands, score: Res<Score>, fonts: Res<FontAssets>) {
             ));
 
             spawn_play_btn(b, 1200, fonts.main.clone());
+
+            if last_replay.0.is_some() {
+                spawn_replay_btn(b, 1300, fonts.main.clone(), systems.play_replay);
+            }
         });
 }
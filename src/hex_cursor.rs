@@ -0,0 +1,289 @@
+use crate::{
+    animation::{get_scale_anim, get_scale_tween, get_translation_anim, get_translation_tween},
+    cooldown::{Cooldown, CursorMoving},
+    input::GameAction,
+    map::{WorldLayout, WorldMap},
+    map_completion::CompletedMap,
+    pause::PauseState,
+    piece::{Carried, LotSlot, Piece, PlacePieceRequest, RotatePieceRequest},
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_tweening::{Animator, EaseFunction, Tracks};
+use hexx::Hex;
+use leafwing_input_manager::prelude::*;
+
+const MOVE_REPEAT_DELAY_MS: u64 = 250;
+const MOVE_REPEAT_MS: u64 = 110;
+const HELD_Z: f32 = 10.;
+const SELECTED_SCALE: f32 = 1.15;
+
+/// Keyboard and gamepad play.
+/// `MoveDir` cycles through the lot or moves the held piece hex by hex, `Move` picks it up or drops it.
+#[derive(Resource, Default)]
+pub struct HexCursor {
+    /// Lot slot of the selected piece.
+    pub slot: usize,
+    /// Piece carried by the cursor.
+    pub held: Option<Entity>,
+    /// Hex the origin of the held piece is at.
+    pub hex: Hex,
+    /// Set once the cursor gets used, so mouse players don't see the selection.
+    pub active: bool,
+}
+
+pub struct HexCursorPlugin;
+impl Plugin for HexCursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HexCursor>().add_systems(
+            Update,
+            (
+                release_missing_piece,
+                cycle_lot.after(release_missing_piece),
+                pick_or_drop_piece.after(cycle_lot),
+                move_held_piece.after(pick_or_drop_piece),
+                rotate_held_piece.after(pick_or_drop_piece),
+                snap_held_piece
+                    .after(move_held_piece)
+                    .after(rotate_held_piece),
+            )
+                .distributive_run_if(
+                    in_state(GameState::Game)
+                        .and_then(resource_exists::<WorldMap>())
+                        .and_then(in_state(PauseState::Running))
+                        .and_then(not(resource_exists::<CompletedMap>())),
+                ),
+        );
+    }
+}
+
+/// Drops the held piece if it got placed by the mouse or despawned by a reset.
+fn release_missing_piece(mut cursor: ResMut<HexCursor>, piece_q: Query<(), With<Piece>>) {
+    if let Some(e) = cursor.held {
+        if piece_q.get(e).is_err() {
+            cursor.held = None;
+        }
+    }
+}
+
+fn cycle_lot(
+    mut cmd: Commands,
+    mut cursor: ResMut<HexCursor>,
+    input: Res<ActionState<GameAction>>,
+    piece_q: Query<(Entity, &LotSlot), With<Piece>>,
+) {
+    if cursor.held.is_some() || !input.just_pressed(GameAction::MoveDir) {
+        return;
+    }
+
+    let Some(dir) = input.axis_pair(GameAction::MoveDir).map(|axis| axis.xy()) else {
+        return;
+    };
+
+    let mut slots: Vec<_> = piece_q.iter().map(|(_, slot)| slot.0).collect();
+    slots.sort();
+
+    if slots.is_empty() {
+        return;
+    }
+
+    let current = slots.iter().position(|slot| *slot >= cursor.slot);
+
+    // the first press only shows the selection
+    cursor.slot = if !cursor.active {
+        slots[current.unwrap_or(0)]
+    } else {
+        let i = current.unwrap_or(0);
+        let step = if dir.y.abs() >= dir.x.abs() {
+            dir.y
+        } else {
+            dir.x
+        };
+
+        if step > 0. {
+            slots[(i + 1) % slots.len()]
+        } else {
+            slots[(i + slots.len() - 1) % slots.len()]
+        }
+    };
+    cursor.active = true;
+
+    for (e, slot) in piece_q.iter() {
+        let scale = if slot.0 == cursor.slot {
+            SELECTED_SCALE
+        } else {
+            1.
+        };
+
+        cmd.entity(e).try_insert(get_scale_anim(
+            None,
+            Vec2::splat(scale).extend(1.),
+            150,
+            EaseFunction::QuadraticOut,
+        ));
+    }
+}
+
+fn pick_or_drop_piece(
+    mut cmd: Commands,
+    mut cursor: ResMut<HexCursor>,
+    input: Res<ActionState<GameAction>>,
+    mut ev_w: EventWriter<PlacePieceRequest>,
+    mut piece_q: Query<(Entity, &LotSlot, &mut Transform), With<Piece>>,
+    map_layout: Res<WorldLayout>,
+) {
+    if !input.just_pressed(GameAction::Move) {
+        return;
+    }
+
+    cursor.active = true;
+
+    if let Some(e) = cursor.held.take() {
+        if let Ok((_, _, mut t)) = piece_q.get_mut(e) {
+            t.translation.z = 1.;
+        }
+
+        ev_w.send(PlacePieceRequest {
+            piece: e,
+            hex: cursor.hex,
+        });
+
+        return;
+    }
+
+    let selected = piece_q
+        .iter()
+        .filter(|(_, slot, _)| slot.0 >= cursor.slot)
+        .min_by_key(|(_, slot, _)| slot.0)
+        .or_else(|| piece_q.iter().max_by_key(|(_, slot, _)| slot.0))
+        .map(|(e, slot, _)| (e, slot.0));
+
+    if let Some((e, slot)) = selected {
+        cursor.slot = slot;
+        cursor.held = Some(e);
+        cursor.hex = Hex::ZERO;
+
+        // drop the selection scale as well
+        cmd.entity(e).try_insert((
+            Carried,
+            Animator::new(Tracks::new([
+                get_translation_tween(
+                    None,
+                    map_layout.hex_to_world_pos(cursor.hex).extend(HELD_Z),
+                    120,
+                    EaseFunction::QuadraticOut,
+                ),
+                get_scale_tween(None, Vec3::ONE, 120, EaseFunction::QuadraticOut),
+            ])),
+        ));
+    }
+}
+
+fn move_held_piece(
+    mut cmd: Commands,
+    mut cursor: ResMut<HexCursor>,
+    input: Res<ActionState<GameAction>>,
+    cooldown_q: Query<(), With<Cooldown<CursorMoving>>>,
+    map: Res<WorldMap>,
+    map_layout: Res<WorldLayout>,
+) {
+    let Some(e) = cursor.held else {
+        return;
+    };
+
+    if !input.pressed(GameAction::MoveDir) || cooldown_q.get(e).is_ok() {
+        return;
+    }
+
+    let Some(dir) = input.axis_pair(GameAction::MoveDir).map(|axis| axis.xy()) else {
+        return;
+    };
+
+    if dir.length() < 0.5 {
+        return;
+    }
+
+    let next = step_hex(&map_layout, cursor.hex, dir);
+
+    // keep the origin on the board, the rest of the piece can hang off the edge
+    if map.hexes.contains_key(&next) {
+        cursor.hex = next;
+        cmd.entity(e).try_insert(get_translation_anim(
+            None,
+            map_layout.hex_to_world_pos(next).extend(HELD_Z),
+            120,
+            EaseFunction::QuadraticOut,
+        ));
+    }
+
+    cmd.entity(e).try_insert(Cooldown::<CursorMoving>::new(
+        if input.just_pressed(GameAction::MoveDir) {
+            MOVE_REPEAT_DELAY_MS
+        } else {
+            MOVE_REPEAT_MS
+        },
+    ));
+}
+
+/// Neighbour of `hex` closest to the `dir` on screen.
+/// Pointy hexes have no neighbour straight up or down, so vertical moves zig-zag between the two candidates.
+fn step_hex(map_layout: &WorldLayout, hex: Hex, dir: Vec2) -> Hex {
+    let origin = map_layout.hex_to_world_pos(hex);
+    let dir = dir.normalize();
+    let dots = hex.all_neighbors().map(|n| {
+        (
+            n,
+            (map_layout.hex_to_world_pos(n) - origin)
+                .normalize()
+                .dot(dir),
+        )
+    });
+    let max_dot = dots.iter().map(|(_, dot)| *dot).fold(f32::MIN, f32::max);
+    let candidates: Vec<_> = dots
+        .iter()
+        .filter(|(_, dot)| max_dot - *dot < 0.01)
+        .map(|(n, _)| *n)
+        .collect();
+
+    candidates[hex.y.rem_euclid(candidates.len() as i32) as usize]
+}
+
+fn rotate_held_piece(
+    cursor: Res<HexCursor>,
+    input: Res<ActionState<GameAction>>,
+    mut ev_w: EventWriter<RotatePieceRequest>,
+) {
+    let Some(e) = cursor.held else {
+        return;
+    };
+
+    let clockwise = if input.just_pressed(GameAction::RotateCw) {
+        true
+    } else if input.just_pressed(GameAction::RotateCcw) {
+        false
+    } else {
+        return;
+    };
+
+    // rotating around the origin keeps the piece under the cursor
+    ev_w.send(RotatePieceRequest {
+        piece: e,
+        pivot: Hex::ZERO,
+        clockwise,
+    });
+}
+
+/// Snaps the held piece the same way dragging does, so dropping it follows the same rules.
+fn snap_held_piece(cursor: Res<HexCursor>, mut piece_q: Query<&mut Piece>, map: Res<WorldMap>) {
+    let Some(e) = cursor.held else {
+        return;
+    };
+
+    if let Ok(mut piece) = piece_q.get_mut(e) {
+        let fits = piece.fits(&map, cursor.hex);
+
+        if fits != (piece.target_hex() == Some(cursor.hex)) {
+            piece.try_target(&map, cursor.hex);
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    daily::DailyRun,
+    game_mode::{GameMode, MARATHON_PAR_SECS},
+    level::TestLevel,
+    loading::FontAssets,
+    map::BoardSeed,
+    pause::AbandonedRun,
+    replay::ReplayPlayback,
+    reset::Resettable,
+    score::{Level, Score},
+    storage::{KeyValueStore, Storage},
+    GameState,
+};
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_tweening::{Animator, EaseFunction};
+use serde::{Deserialize, Serialize};
+
+const HIGH_SCORES_KEY: &str = "high_scores.ron";
+pub const MAX_HIGH_SCORES: usize = 10;
+/// Number of entries of the mode listed on the game over screen.
+const SHOWN_HIGH_SCORES: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    pub level: u32,
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    pub seed: u64,
+    pub mode: String,
+}
+
+impl HighScoreEntry {
+    /// Date of the run formatted as `YYYY-MM-DD` (UTC).
+    pub fn date(&self) -> String {
+        format_date(self.timestamp / 86_400)
+    }
+
+    /// How the run went in `mode`, marathons are scored by the seconds left of their par.
+    pub fn result(&self, mode: GameMode) -> String {
+        match mode {
+            // every marathon ends at the same level, so only the time tells them apart
+            GameMode::Marathon => format!(
+                "TIME {}s",
+                (MARATHON_PAR_SECS as u32).saturating_sub(self.score)
+            ),
+            _ => format!("{}  LVL {}", self.score, self.level),
+        }
+    }
+}
+
+/// Formats days since the Unix epoch as `YYYY-MM-DD`.
+pub fn format_date(days: u64) -> String {
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_part = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_part + 2) / 5 + 1;
+    let month = if month_part < 10 {
+        month_part + 3
+    } else {
+        month_part - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Best runs ordered from the highest score.
+/// Modes are scored differently, so each keeps its own [`MAX_HIGH_SCORES`] entries.
+#[derive(Debug, Resource, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn load(store: &dyn KeyValueStore) -> Self {
+        store
+            .load(HIGH_SCORES_KEY)
+            .and_then(|data| match ron::from_str(&data) {
+                Ok(scores) => Some(scores),
+                Err(e) => {
+                    warn!("Failed to parse high scores: {e}");
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, store: &mut dyn KeyValueStore) {
+        match ron::to_string(self) {
+            Ok(data) => store.save(HIGH_SCORES_KEY, &data),
+            Err(e) => warn!("Failed to serialize high scores: {e}"),
+        }
+    }
+
+    /// Adds the entry and returns its rank among the runs of its mode (0 being the best)
+    /// if it made it to the table.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        // ties keep the older run above the new one
+        let rank = self
+            .mode_entries(&entry.mode)
+            .take_while(|e| e.score >= entry.score)
+            .count();
+
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        let mode = entry.mode.clone();
+        self.entries.insert(index, entry);
+
+        if let Some(dropped) = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.mode == mode)
+            .nth(MAX_HIGH_SCORES)
+            .map(|(i, _)| i)
+        {
+            self.entries.remove(dropped);
+        }
+
+        Some(rank)
+    }
+
+    /// Entries of the mode ordered from the highest score.
+    pub fn ranking(&self, mode: GameMode) -> impl Iterator<Item = &HighScoreEntry> {
+        self.mode_entries(mode.label())
+    }
+
+    pub fn best(&self, mode: GameMode) -> Option<&HighScoreEntry> {
+        self.ranking(mode).next()
+    }
+
+    fn mode_entries<'a>(&'a self, mode: &'a str) -> impl Iterator<Item = &'a HighScoreEntry> {
+        self.entries.iter().filter(move |e| e.mode == mode)
+    }
+}
+
+/// Rank of the last finished run among the runs of its mode in [`HighScores`].
+#[derive(Debug, Resource, Default)]
+pub struct LastRun {
+    pub rank: Option<usize>,
+}
+
+pub struct HighScoresPlugin;
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Storage>()
+            .init_resource::<LastRun>()
+            .add_systems(Startup, load_high_scores)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (record_run, setup_game_over_ui).chain(),
+            )
+            .add_systems(OnEnter(GameState::Tutorial), setup_tutorial_ui)
+            .add_systems(
+                Update,
+                update_best_text
+                    .run_if(in_state(GameState::Tutorial).and_then(resource_changed::<GameMode>())),
+            );
+    }
+}
+
+/// Unix timestamp in seconds.
+pub fn now_secs() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    // SystemTime isn't available in the browser
+    #[cfg(target_arch = "wasm32")]
+    return (js_sys::Date::now() / 1000.) as u64;
+}
+
+fn load_high_scores(mut cmd: Commands, storage: Res<Storage>) {
+    cmd.insert_resource(HighScores::load(storage.0.as_ref()));
+}
+
+fn record_run(
+    score: Res<Score>,
+    level: Res<Level>,
+    seed: Res<BoardSeed>,
+    mode: Res<GameMode>,
+    test_level: Option<Res<TestLevel>>,
+    playback: Option<Res<ReplayPlayback>>,
+    daily: Option<Res<DailyRun>>,
+    abandoned: Option<Res<AbandonedRun>>,
+    mut high_scores: ResMut<HighScores>,
+    mut last_run: ResMut<LastRun>,
+    mut storage: ResMut<Storage>,
+) {
+    // test plays of edited levels, replays, daily practice and abandoned runs aren't real runs
+    if test_level.is_some()
+        || playback.is_some()
+        || abandoned.is_some()
+        || daily.map_or(false, |daily| !daily.scored)
+    {
+        last_run.rank = None;
+        return;
+    }
+
+    last_run.rank = high_scores.insert(HighScoreEntry {
+        score: score.0,
+        level: level.0,
+        timestamp: now_secs(),
+        seed: seed.seed,
+        mode: mode.label().to_string(),
+    });
+
+    if last_run.rank.is_some() {
+        high_scores.save(storage.0.as_mut());
+    }
+}
+
+#[derive(Component)]
+struct BestText;
+
+pub fn spawn_text<'w, 's, 'a>(
+    b: &'a mut ChildBuilder<'w, 's, '_>,
+    text: String,
+    style: TextStyle,
+    delay_ms: u64,
+) -> EntityCommands<'w, 's, 'a> {
+    b.spawn((
+        TextBundle {
+            transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+            ..TextBundle::from_section(text, style).with_text_alignment(TextAlignment::Center)
+        },
+        Animator::new(delay_tween(
+            get_scale_tween(None, Vec3::ONE, 350, EaseFunction::BackOut),
+            delay_ms,
+        )),
+        Resettable,
+    ))
+}
+
+fn setup_game_over_ui(
+    mut cmd: Commands,
+    high_scores: Res<HighScores>,
+    last_run: Res<LastRun>,
+    mode: Res<GameMode>,
+    fonts: Res<FontAssets>,
+) {
+    let text_style = |font_size: f32, color: Color| TextStyle {
+        font_size,
+        color,
+        font: fonts.main.clone(),
+    };
+    let color = Color::rgb_u8(61, 51, 51);
+    let highlight = Color::rgb_u8(214, 133, 38);
+
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                bottom: Val::Px(30.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        Resettable,
+    ))
+    .with_children(|b| {
+        if last_run.rank == Some(0) {
+            spawn_text(b, "NEW BEST".to_string(), text_style(50., highlight), 1000);
+        }
+
+        for (i, entry) in high_scores
+            .ranking(*mode)
+            .take(SHOWN_HIGH_SCORES)
+            .enumerate()
+        {
+            spawn_text(
+                b,
+                format!("{}. {}  {}", i + 1, entry.result(*mode), entry.date()),
+                text_style(
+                    25.,
+                    if last_run.rank == Some(i) {
+                        highlight
+                    } else {
+                        color
+                    },
+                ),
+                1400 + i as u64 * 100,
+            );
+        }
+    });
+}
+
+fn best_text(high_scores: &HighScores, mode: GameMode) -> String {
+    high_scores
+        .best(mode)
+        .map_or(String::new(), |best| format!("BEST: {}", best.result(mode)))
+}
+
+fn setup_tutorial_ui(
+    mut cmd: Commands,
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+    fonts: Res<FontAssets>,
+) {
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                top: Val::Px(30.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        },
+        Resettable,
+    ))
+    .with_children(|b| {
+        spawn_text(
+            b,
+            best_text(&high_scores, *mode),
+            TextStyle {
+                font_size: 35.,
+                color: Color::rgb_u8(61, 51, 51),
+                font: fonts.main.clone(),
+            },
+            1500,
+        )
+        .insert(BestText);
+    });
+}
+
+fn update_best_text(
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+    mut text_q: Query<&mut Text, With<BestText>>,
+) {
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = best_text(&high_scores, *mode);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    animation::get_translation_anim,
+    board::BoardLayout,
+    input::GameAction,
+    map::WorldMap,
+    map_completion::CompletedMap,
+    pause::PauseState,
+    piece::{
+        hide_piece, show_piece, HiddenPiece, InitialPosition, Piece, PlacePieceRequest, PlacedPiece,
+    },
+    reset::{Resettable, ResettableGrid},
+    score::UpdateTimerEv,
+    stash::Stashed,
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+use bevy_tweening::EaseFunction;
+use hexx::Hex;
+use leafwing_input_manager::prelude::*;
+
+/// A piece placed on the board.
+pub struct Placement {
+    pub piece: Entity,
+    pub hex: Hex,
+    /// The piece as it was placed.
+    pub data: Piece,
+    pub lot_position: Vec3,
+    /// Leftover lot pieces hidden by the placement.
+    pub discarded: Vec<Entity>,
+    /// Lot offered after the placement, hidden again by undoing it.
+    pub next_lot: Vec<Entity>,
+    /// Whether the piece got placed from the hold slot, undoing the placement parks it there again.
+    pub from_stash: bool,
+}
+
+/// Placements of the current board.
+/// Placing the piece on top of the redo stack again counts as a redo, any other placement clears the stack.
+#[derive(Resource, Default)]
+pub struct PlacementHistory {
+    pub undo: Vec<Placement>,
+    pub redo: Vec<Placement>,
+}
+
+#[derive(Resource)]
+pub struct UndoSettings {
+    pub enabled: bool,
+    /// Seconds taken off the timer for every undo.
+    pub time_cost: f32,
+}
+
+impl Default for UndoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            time_cost: 2.,
+        }
+    }
+}
+
+#[derive(Debug, Event, Clone, Copy)]
+pub struct UndoRequest;
+
+#[derive(Debug, Event, Clone, Copy)]
+pub struct RedoRequest;
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlacementHistory>()
+            .init_resource::<UndoSettings>()
+            .add_event::<UndoRequest>()
+            .add_event::<RedoRequest>()
+            .add_systems(
+                Update,
+                clear_history.run_if(resource_exists_and_changed::<BoardLayout>()),
+            )
+            .add_systems(
+                Update,
+                (
+                    request_undo,
+                    undo_placement.after(request_undo),
+                    redo_placement.after(request_undo),
+                )
+                    .distributive_run_if(
+                        in_state(GameState::Game)
+                            .and_then(resource_exists::<WorldMap>())
+                            .and_then(in_state(PauseState::Running))
+                            .and_then(not(resource_exists::<CompletedMap>())),
+                    ),
+            );
+    }
+}
+
+fn clear_history(mut history: ResMut<PlacementHistory>) {
+    history.undo.clear();
+    history.redo.clear();
+}
+
+fn request_undo(
+    input: Res<ActionState<GameAction>>,
+    mut undo_w: EventWriter<UndoRequest>,
+    mut redo_w: EventWriter<RedoRequest>,
+) {
+    if input.just_pressed(GameAction::Undo) {
+        undo_w.send(UndoRequest);
+    } else if input.just_pressed(GameAction::Redo) {
+        redo_w.send(RedoRequest);
+    }
+}
+
+fn undo_placement(
+    mut cmd: Commands,
+    mut ev_r: EventReader<UndoRequest>,
+    mut timer_ev_w: EventWriter<UpdateTimerEv>,
+    mut history: ResMut<PlacementHistory>,
+    mut map: ResMut<WorldMap>,
+    settings: Res<UndoSettings>,
+    lot_q: Query<(Entity, &Piece), Without<Stashed>>,
+    hidden_q: Query<&HiddenPiece>,
+    children_q: Query<&Children>,
+) {
+    // lot pieces can't be queried again until the commands are applied, so only one undo per frame
+    if ev_r.read().count() == 0 || !settings.enabled {
+        return;
+    }
+
+    let Some(mut placement) = history.undo.pop() else {
+        return;
+    };
+
+    // swap the lot offered after the placement for the one it was placed from
+    if !placement.discarded.is_empty() {
+        placement.next_lot = lot_q
+            .iter()
+            .map(|(e, piece)| {
+                hide_piece(&mut cmd, e, piece);
+                e
+            })
+            .collect();
+
+        for e in placement.discarded.iter() {
+            if let Ok(hidden) = hidden_q.get(*e) {
+                show_piece(&mut cmd, *e, &hidden.0);
+            }
+        }
+
+        placement.discarded.clear();
+    }
+
+    map.remove_piece(placement.data.hexes().map(|(hex, _)| placement.hex + *hex));
+
+    cmd.entity(placement.piece)
+        .remove::<PlacedPiece>()
+        .try_insert((
+            placement.data.clone(),
+            InitialPosition(placement.lot_position),
+            get_translation_anim(
+                None,
+                placement.lot_position,
+                250,
+                EaseFunction::QuadraticOut,
+            ),
+        ));
+
+    if placement.from_stash {
+        cmd.entity(placement.piece)
+            .remove::<ResettableGrid>()
+            .try_insert((Stashed, Resettable));
+    }
+
+    if let Ok(children) = children_q.get(placement.piece) {
+        for child in children.iter() {
+            cmd.entity(*child).try_insert(Pickable::default());
+        }
+    }
+
+    if settings.time_cost > 0. {
+        timer_ev_w.send(UpdateTimerEv(-settings.time_cost));
+    }
+
+    history.redo.push(placement);
+}
+
+fn redo_placement(
+    mut ev_r: EventReader<RedoRequest>,
+    mut ev_w: EventWriter<PlacePieceRequest>,
+    history: Res<PlacementHistory>,
+    settings: Res<UndoSettings>,
+) {
+    if ev_r.read().count() == 0 || !settings.enabled {
+        return;
+    }
+
+    if let Some(placement) = history.redo.last() {
+        ev_w.send(PlacePieceRequest {
+            piece: placement.piece,
+            hex: placement.hex,
+        });
+    }
+}
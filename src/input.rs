@@ -1,14 +1,89 @@
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::GameState;
 
-#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
 pub enum GameAction {
     Move,
     MoveDir,
     RotateCw,
     RotateCcw,
+    Undo,
+    Redo,
+    Stash,
+    Pause,
+}
+
+/// Keyboard keys of the actions that can be rebound.
+/// Gamepad buttons, the mouse wheel and the movement keys stay fixed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings(pub Vec<(GameAction, KeyCode)>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(vec![
+            (GameAction::Move, KeyCode::Space),
+            (GameAction::RotateCcw, KeyCode::Q),
+            (GameAction::RotateCw, KeyCode::E),
+            (GameAction::Undo, KeyCode::Z),
+            (GameAction::Redo, KeyCode::Y),
+            (GameAction::Stash, KeyCode::H),
+            (GameAction::Pause, KeyCode::Escape),
+        ])
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: GameAction) -> Option<KeyCode> {
+        self.0
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, key)| *key)
+    }
+
+    /// Name of the key bound to `action`, as shown to the player.
+    pub fn key_label(&self, action: GameAction) -> String {
+        self.key(action)
+            .map_or("-".to_string(), |key| format!("{key:?}").to_uppercase())
+    }
+
+    /// Binds `key` to `action`, an action already bound to `key` gets the previous key of `action`.
+    pub fn rebind(&mut self, action: GameAction, key: KeyCode) {
+        let previous = self.key(action);
+
+        for (a, k) in self.0.iter_mut() {
+            if *a == action {
+                *k = key;
+            } else if *k == key {
+                if let Some(previous) = previous {
+                    *k = previous;
+                }
+            }
+        }
+    }
+
+    pub fn input_map(&self) -> InputMap<GameAction> {
+        let mut map = InputMap::default();
+        map.insert(DualAxis::left_stick(), GameAction::MoveDir)
+            .insert(VirtualDPad::dpad(), GameAction::MoveDir)
+            .insert(VirtualDPad::arrow_keys(), GameAction::MoveDir)
+            .insert(VirtualDPad::wasd(), GameAction::MoveDir)
+            .insert(GamepadButtonType::South, GameAction::Move)
+            .insert(MouseWheelDirection::Down, GameAction::RotateCcw)
+            .insert(MouseWheelDirection::Up, GameAction::RotateCw)
+            .insert(GamepadButtonType::West, GameAction::Undo)
+            .insert(GamepadButtonType::North, GameAction::Redo)
+            .insert(GamepadButtonType::East, GameAction::Stash)
+            .insert(GamepadButtonType::Start, GameAction::Pause);
+
+        for (action, key) in self.0.iter() {
+            map.insert(*key, *action);
+        }
+
+        map
+    }
 }
 
 pub struct InputPlugin;
@@ -16,19 +91,6 @@ impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(InputManagerPlugin::<GameAction>::default())
             .init_resource::<ActionState<GameAction>>()
-            .insert_resource(
-                InputMap::default()
-                    .insert(DualAxis::left_stick(), GameAction::MoveDir)
-                    .insert(VirtualDPad::dpad(), GameAction::MoveDir)
-                    .insert(VirtualDPad::arrow_keys(), GameAction::MoveDir)
-                    .insert(VirtualDPad::wasd(), GameAction::MoveDir)
-                    .insert(GamepadButtonType::South, GameAction::Move)
-                    .insert(KeyCode::Space, GameAction::Move)
-                    .insert(KeyCode::Q, GameAction::RotateCcw)
-                    .insert(MouseWheelDirection::Down, GameAction::RotateCcw)
-                    .insert(KeyCode::E, GameAction::RotateCw)
-                    .insert(MouseWheelDirection::Up, GameAction::RotateCw)
-                    .build(),
-            );
+            .insert_resource(KeyBindings::default().input_map());
     }
 }
@@ -0,0 +1,228 @@
+use crate::{
+    board::BoardLayout,
+    lot_queue::{LotQueue, LOT_SIZE},
+    piece::{HexBlueprints, PieceHexSpec},
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::{BoxedFuture, HashSet},
+};
+use hexx::{shapes, Hex};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Hex of a level file, either a pre-placed route hex (`hex` being its board position)
+/// or a hex of a scripted piece (`hex` being its offset from the piece origin).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelHex {
+    pub hex: (i32, i32),
+    /// Index into [`crate::piece::HexBlueprints`], `None` for an empty hex.
+    #[serde(default)]
+    pub blueprint: Option<usize>,
+    #[serde(default)]
+    pub rotation: usize,
+}
+
+impl From<LevelHex> for PieceHexSpec {
+    fn from(hex: LevelHex) -> Self {
+        PieceHexSpec {
+            offset: Hex::new(hex.hex.0, hex.hex.1),
+            blueprint: hex.blueprint,
+            rotation: hex.rotation,
+        }
+    }
+}
+
+impl From<PieceHexSpec> for LevelHex {
+    fn from(hex: PieceHexSpec) -> Self {
+        LevelHex {
+            hex: (hex.offset.x, hex.offset.y),
+            blueprint: hex.blueprint,
+            rotation: hex.rotation,
+        }
+    }
+}
+
+/// Hand-authored hive stored as a `.level.ron` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Asset, TypePath)]
+#[serde(default)]
+pub struct LevelAsset {
+    /// Radius of the hexagonal grid, the level consists of `hexes` only when missing.
+    pub map_radius: Option<u32>,
+    /// Playable hexes on top of the grid.
+    pub hexes: Vec<(i32, i32)>,
+    pub houses: Vec<(i32, i32)>,
+    pub blocked: Vec<(i32, i32)>,
+    pub routes: Vec<LevelHex>,
+    /// Lots offered in order, generated lots are offered when missing.
+    pub lots: Option<Vec<Vec<Vec<LevelHex>>>>,
+}
+
+impl LevelAsset {
+    /// Parses the level and checks its lots fit the lot slots.
+    pub fn from_ron(data: &str) -> Result<Self, LevelLoaderError> {
+        let level: Self = ron::from_str(data)?;
+
+        if let Some((lot, pieces)) = level
+            .lots
+            .iter()
+            .flatten()
+            .map(|lot| lot.len())
+            .enumerate()
+            .find(|(_, pieces)| *pieces > LOT_SIZE)
+        {
+            return Err(LevelLoaderError::LotSize { lot, pieces });
+        }
+
+        Ok(level)
+    }
+
+    /// Checks the route and lot hexes only use blueprints that exist.
+    /// The blueprints come from the difficulty config, so levels can't be checked while loading.
+    pub fn validate(&self, blueprints: &HexBlueprints) -> Result<(), LevelLoaderError> {
+        let lot_hexes = self.lots.iter().flatten().flatten().flatten();
+
+        match self
+            .routes
+            .iter()
+            .chain(lot_hexes)
+            .filter_map(|hex| hex.blueprint)
+            .find(|bp| *bp >= blueprints.blueprint_count())
+        {
+            Some(blueprint) => Err(LevelLoaderError::UnknownBlueprint(blueprint)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn board(&self) -> BoardLayout {
+        let to_hexes = |hexes: &[(i32, i32)]| -> Vec<Hex> {
+            hexes.iter().map(|(x, y)| Hex::new(*x, *y)).collect()
+        };
+        let houses = to_hexes(&self.houses);
+        let blocked = to_hexes(&self.blocked);
+        let routes: Vec<PieceHexSpec> = self.routes.iter().map(|hex| (*hex).into()).collect();
+        let map_radius = self.map_radius.unwrap_or_else(|| {
+            self.hexes
+                .iter()
+                .map(|(x, y)| Hex::new(*x, *y).ulength())
+                .max()
+                .unwrap_or(0)
+        });
+
+        let occupied: HashSet<_> = houses.iter().chain(blocked.iter()).copied().collect();
+        let mut playable = Vec::new();
+
+        // the grid goes first, so the rest of the hexes gets treated as padding
+        for hex in self
+            .map_radius
+            .map_or(Vec::new(), |radius| {
+                shapes::hexagon(Hex::ZERO, radius).collect()
+            })
+            .into_iter()
+            .chain(to_hexes(&self.hexes))
+            .chain(routes.iter().map(|route| route.offset))
+        {
+            if !occupied.contains(&hex) && !playable.contains(&hex) {
+                playable.push(hex);
+            }
+        }
+
+        BoardLayout {
+            map_radius,
+            playable,
+            houses,
+            blocked,
+            routes,
+        }
+    }
+
+    pub fn lots(&self) -> Option<LotQueue> {
+        self.lots.as_ref().map(|lots| {
+            LotQueue::fixed(lots.iter().map(|lot| {
+                lot.iter()
+                    .map(|piece| piece.iter().map(|hex| (*hex).into()).collect())
+                    .collect()
+            }))
+        })
+    }
+}
+
+/// Level played instead of every generated board, used to test play edited levels.
+#[derive(Debug, Resource)]
+pub struct TestLevel(pub LevelAsset);
+
+#[derive(Debug)]
+pub enum LevelLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    UnknownBlueprint(usize),
+    LotSize { lot: usize, pieces: usize },
+}
+
+impl fmt::Display for LevelLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelLoaderError::Io(e) => write!(f, "Failed to read the level: {e}"),
+            LevelLoaderError::Ron(e) => write!(f, "Failed to parse the level: {e}"),
+            LevelLoaderError::UnknownBlueprint(bp) => {
+                write!(f, "The level uses blueprint {bp}, which doesn't exist")
+            }
+            LevelLoaderError::LotSize { lot, pieces } => {
+                write!(
+                    f,
+                    "Lot {lot} has {pieces} pieces, lots can't have more than {LOT_SIZE}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LevelLoaderError {}
+
+impl From<std::io::Error> for LevelLoaderError {
+    fn from(e: std::io::Error) -> Self {
+        LevelLoaderError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for LevelLoaderError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        LevelLoaderError::Ron(e)
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<LevelAsset, LevelLoaderError>> {
+        Box::pin(async move {
+            let mut data = String::new();
+            reader.read_to_string(&mut data).await?;
+
+            LevelAsset::from_ron(&data)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+pub struct LevelPlugin;
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelAsset>()
+            .init_asset_loader::<LevelLoader>();
+    }
+}
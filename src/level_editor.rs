@@ -0,0 +1,507 @@
+use crate::{
+    level::{LevelAsset, LevelHex, TestLevel},
+    loading::{FontAssets, MainCam, TextureAssets},
+    map::WorldLayout,
+    menu::{ButtonColors, RunSystem},
+    mouse::CursorPosition,
+    piece::{HexBlueprints, PieceHexSpec},
+    GameState,
+};
+use bevy::{ecs::system::SystemId, prelude::*, utils::HashMap};
+use hexx::{shapes, Hex};
+use leafwing_input_manager::prelude::*;
+use strum::{EnumIter, IntoEnumIterator};
+
+const EDITOR_LEVEL_PATH: &str = "assets/levels/editor.level.ron";
+/// Radius of the empty canvas shown around the edited level.
+const CANVAS_RADIUS: u32 = 6;
+
+#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+pub enum EditorAction {
+    Paint,
+    Erase,
+    NextTool,
+    NextBlueprint,
+    Rotate,
+    Save,
+    Load,
+    TestPlay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+enum EditorTool {
+    #[default]
+    Playable,
+    Blocked,
+    House,
+    Route,
+}
+
+impl EditorTool {
+    fn label(&self) -> &'static str {
+        match self {
+            EditorTool::Playable => "PLAYABLE",
+            EditorTool::Blocked => "BLOCKED",
+            EditorTool::House => "HOUSE",
+            EditorTool::Route => "ROUTE",
+        }
+    }
+
+    fn next(&self) -> Self {
+        EditorTool::iter()
+            .cycle()
+            .skip_while(|tool| tool != self)
+            .nth(1)
+            .unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditorHex {
+    Playable,
+    Blocked,
+    House,
+    /// Pre-placed route hex, its offset being the board hex.
+    Route(PieceHexSpec),
+}
+
+/// Level being edited, kept around while test playing it.
+#[derive(Debug, Resource, Default)]
+pub struct EditedLevel {
+    hexes: HashMap<Hex, EditorHex>,
+    /// Scripted lots of a loaded level, they can't be edited yet.
+    lots: Option<Vec<Vec<Vec<LevelHex>>>>,
+}
+
+impl EditedLevel {
+    pub fn from_level(level: &LevelAsset) -> Self {
+        let board = level.board();
+        let mut hexes: HashMap<_, _> = board
+            .playable
+            .iter()
+            .map(|hex| (*hex, EditorHex::Playable))
+            .collect();
+        hexes.extend(board.houses.iter().map(|hex| (*hex, EditorHex::House)));
+        hexes.extend(board.blocked.iter().map(|hex| (*hex, EditorHex::Blocked)));
+        hexes.extend(
+            board
+                .routes
+                .iter()
+                .map(|route| (route.offset, EditorHex::Route(*route))),
+        );
+
+        Self {
+            hexes,
+            lots: level.lots.clone(),
+        }
+    }
+
+    pub fn to_level(&self) -> LevelAsset {
+        // sorted so saving the same level twice produces the same file
+        let mut hexes: Vec<_> = self.hexes.iter().collect();
+        hexes.sort_by_key(|(hex, _)| (hex.x, hex.y));
+
+        let mut level = LevelAsset {
+            lots: self.lots.clone(),
+            ..default()
+        };
+
+        for (hex, kind) in hexes {
+            let pos = (hex.x, hex.y);
+
+            match kind {
+                EditorHex::Playable => level.hexes.push(pos),
+                EditorHex::Blocked => level.blocked.push(pos),
+                EditorHex::House => level.houses.push(pos),
+                EditorHex::Route(route) => {
+                    level.hexes.push(pos);
+                    level.routes.push((*route).into());
+                }
+            }
+        }
+
+        level
+    }
+}
+
+#[derive(Debug, Resource, Default)]
+struct EditorBrush {
+    tool: EditorTool,
+    blueprint: usize,
+    rotation: usize,
+}
+
+impl EditorBrush {
+    fn hex(&self, hex: Hex) -> EditorHex {
+        match self.tool {
+            EditorTool::Playable => EditorHex::Playable,
+            EditorTool::Blocked => EditorHex::Blocked,
+            EditorTool::House => EditorHex::House,
+            EditorTool::Route => EditorHex::Route(PieceHexSpec {
+                offset: hex,
+                blueprint: Some(self.blueprint),
+                rotation: self.rotation,
+            }),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct EditorSystems {
+    save: SystemId,
+    load: SystemId,
+    test_play: SystemId,
+}
+
+#[derive(Component)]
+struct EditorEntity;
+
+#[derive(Component)]
+struct EditorTile;
+
+#[derive(Component)]
+struct EditorCursor;
+
+#[derive(Component)]
+struct EditorText;
+
+pub struct LevelEditorPlugin;
+impl Plugin for LevelEditorPlugin {
+    fn build(&self, app: &mut App) {
+        let systems = EditorSystems {
+            save: app.world.register_system(save_level),
+            load: app.world.register_system(load_level),
+            test_play: app.world.register_system(test_play),
+        };
+
+        app.add_plugins(InputManagerPlugin::<EditorAction>::default())
+            .init_resource::<ActionState<EditorAction>>()
+            .insert_resource(
+                InputMap::default()
+                    .insert(MouseButton::Left, EditorAction::Paint)
+                    .insert(MouseButton::Right, EditorAction::Erase)
+                    .insert(KeyCode::Tab, EditorAction::NextTool)
+                    .insert(KeyCode::B, EditorAction::NextBlueprint)
+                    .insert(KeyCode::R, EditorAction::Rotate)
+                    .insert(KeyCode::F5, EditorAction::Save)
+                    .insert(KeyCode::F9, EditorAction::Load)
+                    .insert(KeyCode::T, EditorAction::TestPlay)
+                    .build(),
+            )
+            .insert_resource(systems)
+            .init_resource::<EditedLevel>()
+            .init_resource::<EditorBrush>()
+            .add_systems(
+                OnEnter(GameState::Editor),
+                (setup_editor, draw_level).chain(),
+            )
+            .add_systems(OnExit(GameState::Editor), cleanup_editor)
+            // the test play ends with its run, retrying or leaving it plays a generated board
+            .add_systems(OnExit(GameState::GameOver), end_test_play)
+            .add_systems(OnEnter(GameState::Tutorial), end_test_play)
+            .add_systems(
+                Update,
+                (
+                    handle_input,
+                    draw_level
+                        .after(handle_input)
+                        .run_if(resource_changed::<EditedLevel>()),
+                    update_cursor.after(handle_input),
+                )
+                    .distributive_run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+fn setup_editor(
+    mut cmd: Commands,
+    fonts: Res<FontAssets>,
+    sprites: Res<TextureAssets>,
+    systems: Res<EditorSystems>,
+    mut cam_q: Query<(&mut OrthographicProjection, &mut Transform), With<MainCam>>,
+) {
+    cmd.remove_resource::<TestLevel>();
+    cmd.insert_resource(WorldLayout::default());
+
+    let (mut projection, mut cam_t) = cam_q.single_mut();
+    projection.scale = 1.75;
+    cam_t.translation.x = 0.;
+
+    cmd.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color: Color::rgba(1., 1., 1., 0.6),
+                ..default()
+            },
+            texture_atlas: sprites.tiles.clone(),
+            ..default()
+        },
+        EditorCursor,
+        EditorEntity,
+    ));
+
+    let text_style = TextStyle {
+        font_size: 25.,
+        color: Color::rgb_u8(61, 51, 51),
+        font: fonts.main.clone(),
+    };
+
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(30.),
+                top: Val::Px(30.),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        },
+        EditorEntity,
+    ))
+    .with_children(|b| {
+        b.spawn((
+            TextBundle::from_section("", text_style.clone()),
+            EditorText,
+        ));
+        b.spawn(TextBundle::from_section(
+            "[LMB] PAINT  [RMB] ERASE  [TAB] TOOL  [B] BLUEPRINT  [R] ROTATE\n[F5] SAVE  [F9] LOAD  [T] TEST PLAY  [F2] EXIT",
+            text_style.clone(),
+        ));
+
+        for (label, system) in [
+            ("SAVE", systems.save),
+            ("LOAD", systems.load),
+            ("TEST PLAY", systems.test_play),
+        ] {
+            let button_colors = ButtonColors::default();
+            b.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(160.0),
+                        height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(15.)),
+                        ..default()
+                    },
+                    background_color: button_colors.normal.into(),
+                    ..default()
+                },
+                button_colors,
+                RunSystem(system),
+            ))
+            .with_children(|b| {
+                b.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 30.,
+                        ..text_style.clone()
+                    },
+                ));
+            });
+        }
+    });
+}
+
+fn cleanup_editor(mut cmd: Commands, editor_q: Query<Entity, With<EditorEntity>>) {
+    for e in editor_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+}
+
+fn handle_input(
+    mut cmd: Commands,
+    input: Res<ActionState<EditorAction>>,
+    cursor_pos: Res<CursorPosition>,
+    layout: Res<WorldLayout>,
+    blueprints: Res<HexBlueprints>,
+    systems: Res<EditorSystems>,
+    button_q: Query<&Interaction, With<Button>>,
+    mut brush: ResMut<EditorBrush>,
+    mut level: ResMut<EditedLevel>,
+) {
+    if input.just_pressed(EditorAction::NextTool) {
+        brush.tool = brush.tool.next();
+    }
+
+    if input.just_pressed(EditorAction::NextBlueprint) {
+        brush.tool = EditorTool::Route;
+        brush.blueprint = (brush.blueprint + 1) % blueprints.blueprint_count();
+    }
+
+    if input.just_pressed(EditorAction::Rotate) {
+        brush.rotation = (brush.rotation + 1) % 6;
+    }
+
+    if input.just_pressed(EditorAction::Save) {
+        cmd.run_system(systems.save);
+    }
+
+    if input.just_pressed(EditorAction::Load) {
+        cmd.run_system(systems.load);
+    }
+
+    if input.just_pressed(EditorAction::TestPlay) {
+        cmd.run_system(systems.test_play);
+    }
+
+    // clicking a button shouldn't paint the hex below it
+    if button_q
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    let hex = layout.world_pos_to_hex(cursor_pos.0);
+
+    if input.pressed(EditorAction::Paint) {
+        let painted = brush.hex(hex);
+
+        if level.hexes.get(&hex) != Some(&painted) {
+            level.hexes.insert(hex, painted);
+        }
+    } else if input.pressed(EditorAction::Erase) && level.hexes.contains_key(&hex) {
+        level.hexes.remove(&hex);
+    }
+}
+
+/// Atlas index, rotation and z of the tiles of a hex, matching the tiles of a spawned board.
+fn hex_tiles(hex: Option<&EditorHex>, blueprints: &HexBlueprints) -> Vec<(usize, usize, f32)> {
+    match hex {
+        None | Some(EditorHex::Playable) => vec![(12, 0, 0.1)],
+        Some(EditorHex::Blocked) => vec![(10, 0, 1.)],
+        Some(EditorHex::House) => vec![(11, 0, 1.)],
+        Some(EditorHex::Route(route)) => {
+            vec![
+                (12, 0, 0.1),
+                (blueprints.atlas_index(route), route.rotation, 1.),
+            ]
+        }
+    }
+}
+
+fn draw_level(
+    mut cmd: Commands,
+    level: Res<EditedLevel>,
+    layout: Res<WorldLayout>,
+    blueprints: Res<HexBlueprints>,
+    sprites: Res<TextureAssets>,
+    tile_q: Query<Entity, With<EditorTile>>,
+) {
+    for e in tile_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+
+    let mut hexes: Vec<_> = shapes::hexagon(Hex::ZERO, CANVAS_RADIUS).collect();
+    hexes.extend(
+        level
+            .hexes
+            .keys()
+            .filter(|hex| hex.ulength() > CANVAS_RADIUS),
+    );
+
+    for hex in hexes {
+        let edited = level.hexes.get(&hex);
+
+        for (atlas_index, rotation, z) in hex_tiles(edited, &blueprints) {
+            cmd.spawn((
+                SpriteSheetBundle {
+                    transform: Transform {
+                        translation: layout.hex_to_world_pos(hex).extend(z),
+                        rotation: Quat::from_rotation_z((rotation as f32 * 60.).to_radians()),
+                        ..default()
+                    },
+                    sprite: TextureAtlasSprite {
+                        index: atlas_index,
+                        // hexes outside of the level are only hinted
+                        color: Color::WHITE.with_a(if edited.is_some() { 1. } else { 0.3 }),
+                        ..default()
+                    },
+                    texture_atlas: sprites.tiles.clone(),
+                    ..default()
+                },
+                EditorTile,
+                EditorEntity,
+            ));
+        }
+    }
+}
+
+fn update_cursor(
+    cursor_pos: Res<CursorPosition>,
+    layout: Res<WorldLayout>,
+    brush: Res<EditorBrush>,
+    blueprints: Res<HexBlueprints>,
+    mut cursor_q: Query<(&mut Transform, &mut TextureAtlasSprite), With<EditorCursor>>,
+    mut text_q: Query<&mut Text, With<EditorText>>,
+) {
+    let hex = layout.world_pos_to_hex(cursor_pos.0);
+
+    if let Ok((mut t, mut sprite)) = cursor_q.get_single_mut() {
+        let (atlas_index, rotation, _) = *hex_tiles(Some(&brush.hex(hex)), &blueprints)
+            .last()
+            .unwrap();
+        t.translation = layout.hex_to_world_pos(hex).extend(5.);
+        t.rotation = Quat::from_rotation_z((rotation as f32 * 60.).to_radians());
+        sprite.index = atlas_index;
+    }
+
+    if let Ok(mut text) = text_q.get_single_mut() {
+        let txt = format!(
+            "TOOL: {}  BLUEPRINT: {}  ROTATION: {}",
+            brush.tool.label(),
+            brush.blueprint,
+            brush.rotation
+        );
+
+        if text.sections[0].value != txt {
+            text.sections[0].value = txt;
+        }
+    }
+}
+
+fn save_level(level: Res<EditedLevel>) {
+    let saved = ron::ser::to_string_pretty(&level.to_level(), default())
+        .map_err(|e| e.to_string())
+        .and_then(|data| std::fs::write(EDITOR_LEVEL_PATH, data).map_err(|e| e.to_string()));
+
+    match saved {
+        Ok(_) => info!("Level saved to {EDITOR_LEVEL_PATH}"),
+        Err(e) => warn!("Failed to save the level: {e}"),
+    }
+}
+
+fn load_level(mut level: ResMut<EditedLevel>, blueprints: Res<HexBlueprints>) {
+    let loaded = std::fs::read_to_string(EDITOR_LEVEL_PATH)
+        .map_err(|e| e.to_string())
+        .and_then(|data| LevelAsset::from_ron(&data).map_err(|e| e.to_string()))
+        .and_then(|loaded| {
+            loaded
+                .validate(&blueprints)
+                .map(|_| loaded)
+                .map_err(|e| e.to_string())
+        });
+
+    match loaded {
+        Ok(loaded) => {
+            *level = EditedLevel::from_level(&loaded);
+            info!("Level loaded from {EDITOR_LEVEL_PATH}");
+        }
+        Err(e) => warn!("Failed to load the level: {e}"),
+    }
+}
+
+fn test_play(
+    mut cmd: Commands,
+    level: Res<EditedLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    cmd.insert_resource(TestLevel(level.to_level()));
+    next_state.set(GameState::Game);
+}
+
+fn end_test_play(mut cmd: Commands) {
+    cmd.remove_resource::<TestLevel>();
+}
@@ -2,37 +2,77 @@
 #![allow(unused_imports)]
 
 mod animation;
+mod audio;
+mod board;
+mod connectivity;
 mod cooldown;
+mod daily;
 mod debug;
+mod difficulty;
 mod ecs;
+mod game_mode;
 mod game_over;
+mod hex_cursor;
+mod high_scores;
+mod history;
 mod input;
+mod level;
+mod level_editor;
 mod loading;
+mod lot_queue;
 mod map;
 mod map_completion;
 mod math;
 mod menu;
 mod mouse;
+mod pause;
 mod piece;
+mod preview;
+mod replay;
 mod reset;
 mod score;
+mod scoring;
+mod settings;
+mod solver;
+mod stash;
+mod storage;
 mod tutorial;
 
+#[cfg(test)]
+mod tests;
+
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
 use crate::piece::PiecePlugin;
 use animation::AnimationPlugin;
+use audio::GameAudioPlugin;
 use bevy::prelude::*;
+use bevy_mod_picking::DefaultPickingPlugins;
 use bevy_trauma_shake::TraumaPlugin;
+use connectivity::ConnectivityPlugin;
 use cooldown::CooldownPlugin;
+use daily::DailyPlugin;
+use difficulty::DifficultyPlugin;
 use ecs::EcsPlugin;
+use game_mode::GameModePlugin;
 use game_over::GameOverPlugin;
+use hex_cursor::HexCursorPlugin;
+use high_scores::HighScoresPlugin;
+use history::HistoryPlugin;
 use input::InputPlugin;
+use level::LevelPlugin;
+use lot_queue::LotQueuePlugin;
 use map::MapPlugin;
 use map_completion::MapCompletionPlugin;
 use mouse::CursorPlugin;
+use pause::PausePlugin;
+use preview::PreviewPlugin;
+use replay::ReplayPlugin;
 use reset::ResetPlugin;
 use score::ScorePlugin;
+use scoring::ScoringPlugin;
+use settings::SettingsPlugin;
+use stash::StashPlugin;
 use tutorial::TutorialPlugin;
 
 // This example game uses States to separate logic
@@ -45,29 +85,51 @@ enum GameState {
     Tutorial,
     Game,
     GameOver,
+    Settings,
+    /// Level editor, only available in debug builds.
+    Editor,
 }
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<GameState>().add_plugins((
-            LoadingPlugin,
-            MenuPlugin,
-            MapPlugin,
-            PiecePlugin,
-            InputPlugin,
-            CursorPlugin,
-            AnimationPlugin,
-            CooldownPlugin,
-            ResetPlugin,
-            MapCompletionPlugin,
-            ScorePlugin,
-            EcsPlugin,
-            TraumaPlugin,
-            GameOverPlugin,
-            TutorialPlugin,
-        ));
+        app.add_state::<GameState>()
+            .add_plugins((
+                LoadingPlugin,
+                MenuPlugin,
+                MapPlugin,
+                PiecePlugin,
+                InputPlugin,
+                CursorPlugin,
+                AnimationPlugin,
+                CooldownPlugin,
+                ResetPlugin,
+                MapCompletionPlugin,
+                ScorePlugin,
+                EcsPlugin,
+                TraumaPlugin,
+                GameOverPlugin,
+                TutorialPlugin,
+            ))
+            .add_plugins((
+                DefaultPickingPlugins,
+                HexCursorPlugin,
+                PreviewPlugin,
+                HistoryPlugin,
+                ConnectivityPlugin,
+                ScoringPlugin,
+                HighScoresPlugin,
+                GameModePlugin,
+                LevelPlugin,
+                DifficultyPlugin,
+                StashPlugin,
+                LotQueuePlugin,
+                ReplayPlugin,
+                DailyPlugin,
+                PausePlugin,
+            ))
+            .add_plugins((SettingsPlugin, GameAudioPlugin));
 
         if cfg!(debug_assertions) {
             app.add_plugins(debug::DebugPlugin);
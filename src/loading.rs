@@ -1,6 +1,9 @@
 use std::ops::Mul;
 
 use crate::{
+    difficulty::DifficultyConfig,
+    game_mode::{GameMode, RunClock, MARATHON_PAR_SECS},
+    level::LevelAsset,
     math::{asymptotic_smoothing, asymptotic_smoothing_with_delta_time, inverse_lerp_clamped},
     score::GameTimer,
     GameState,
@@ -27,9 +30,11 @@ impl Plugin for LoadingPlugin {
                 GameState::Tutorial
             },
         ))
-        // .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
         .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
         .add_collection_to_loading_state::<_, FontAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, LevelAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, ConfigAssets>(GameState::Loading)
         .add_systems(OnEnter(GameState::Loading), (spawn_cam, spawn_bg))
         .add_systems(Update, (move_bg));
     }
@@ -38,11 +43,11 @@ impl Plugin for LoadingPlugin {
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see <https://github.com/NiklasEi/bevy_asset_loader>)
 
-// #[derive(AssetCollection, Resource)]
-// pub struct AudioAssets {
-//     #[asset(path = "audio/flying.ogg")]
-//     pub flying: Handle<AudioSource>,
-// }
+#[derive(AssetCollection, Resource)]
+pub struct AudioAssets {
+    #[asset(path = "audio/flying.ogg")]
+    pub music: Handle<AudioSource>,
+}
 
 #[derive(AssetCollection, Resource)]
 pub struct FontAssets {
@@ -61,6 +66,21 @@ pub struct TextureAssets {
     pub tiles: Handle<TextureAtlas>,
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(
+        paths("levels/puzzle_1.level.ron", "levels/puzzle_2.level.ron"),
+        collection(typed)
+    )]
+    pub puzzles: Vec<Handle<LevelAsset>>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ConfigAssets {
+    #[asset(path = "config/default.difficulty.ron")]
+    pub difficulty: Handle<DifficultyConfig>,
+}
+
 fn spawn_cam(mut cmd: Commands) {
     cmd.spawn((Camera2dBundle::default(), MainCam, Shake::default()));
 }
@@ -100,20 +120,24 @@ fn move_bg(
     time: Res<Time>,
     mut bg_q: Query<(&mut Transform, &Bg)>,
     timer: Option<Res<GameTimer>>,
+    mode: Res<GameMode>,
+    clock: Res<RunClock>,
     mut speed_t: Local<f32>,
 ) {
-    *speed_t = asymptotic_smoothing_with_delta_time(
-        *speed_t,
-        30. + timer.map_or(0., |t| {
+    let speed_up = match *mode {
+        GameMode::Timed | GameMode::Daily => timer.map_or(0., |t| {
             if t.finished() {
                 0.
             } else {
                 t.0.percent() * 300.
             }
         }),
-        0.09,
-        time.delta_seconds(),
-    );
+        GameMode::Marathon => (clock.elapsed_secs() / MARATHON_PAR_SECS).min(1.) * 300.,
+        GameMode::Zen | GameMode::Puzzle => 0.,
+    };
+
+    *speed_t =
+        asymptotic_smoothing_with_delta_time(*speed_t, 30. + speed_up, 0.09, time.delta_seconds());
 
     for (mut t, bg) in bg_q.iter_mut() {
         t.translation.x = time.elapsed_seconds().mul(*speed_t).rem_euclid(1000.) + bg.0;
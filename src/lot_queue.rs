@@ -0,0 +1,180 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    loading::{FontAssets, TextureAssets},
+    map::{WorldLayout, WorldMap, HEX_WIDTH},
+    map_completion::CompletedMap,
+    piece::{generate_piece, piece_centre, HexBlueprints, PieceHexSpec, LOT_SLOT_SPACING},
+    reset::ResettableGrid,
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_tweening::{Animator, EaseFunction};
+use hexx::Hex;
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// Pieces offered at once, there's a slot for each.
+pub const LOT_SIZE: usize = 3;
+/// Lots kept ahead of the offered one, so there's always one to preview.
+const QUEUED_LOTS: usize = 1;
+const PREVIEW_SCALE: f32 = 0.45;
+
+/// Pieces of a lot that's yet to be spawned.
+pub type LotSpec = Vec<Vec<PieceHexSpec>>;
+
+/// Upcoming lots, the front one gets offered next.
+/// Generated queues get topped up from the board rng, fixed ones (puzzles) run out instead.
+#[derive(Debug, Resource, Default, Clone)]
+pub struct LotQueue {
+    lots: VecDeque<LotSpec>,
+    fixed: bool,
+}
+
+impl LotQueue {
+    /// Offers these lots in order, once they run out no more lots get offered.
+    pub fn fixed(lots: impl IntoIterator<Item = LotSpec>) -> Self {
+        Self {
+            lots: lots.into_iter().collect(),
+            fixed: true,
+        }
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.fixed
+    }
+
+    /// Lot offered after the current one.
+    pub fn peek(&self) -> Option<&LotSpec> {
+        self.lots.front()
+    }
+
+    pub fn lots(&self) -> impl Iterator<Item = &LotSpec> {
+        self.lots.iter()
+    }
+
+    /// Takes the lot to offer and generates the ones to preview.
+    pub fn pop(
+        &mut self,
+        blueprints: &HexBlueprints,
+        level: u32,
+        rng: &mut impl Rng,
+    ) -> Option<LotSpec> {
+        self.top_up(blueprints, level, rng, 1);
+        let lot = self.lots.pop_front();
+        self.top_up(blueprints, level, rng, QUEUED_LOTS);
+
+        lot
+    }
+
+    fn top_up(&mut self, blueprints: &HexBlueprints, level: u32, rng: &mut impl Rng, len: usize) {
+        if self.fixed {
+            return;
+        }
+
+        while self.lots.len() < len {
+            self.lots.push_back(
+                (0..3)
+                    .map(|_| generate_piece(blueprints, level, rng))
+                    .collect(),
+            );
+        }
+    }
+}
+
+#[derive(Component)]
+struct LotPreview;
+
+pub struct LotQueuePlugin;
+impl Plugin for LotQueuePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LotQueue>().add_systems(
+            Update,
+            spawn_preview.run_if(
+                in_state(GameState::Game)
+                    .and_then(resource_exists::<WorldMap>())
+                    .and_then(not(resource_exists::<CompletedMap>()))
+                    .and_then(resource_changed::<LotQueue>()),
+            ),
+        );
+    }
+}
+
+/// Shows a smaller copy of the next lot beside the offered one.
+fn spawn_preview(
+    mut cmd: Commands,
+    queue: Res<LotQueue>,
+    preview_q: Query<Entity, With<LotPreview>>,
+    map: Res<WorldMap>,
+    map_layout: Res<WorldLayout>,
+    blueprints: Res<HexBlueprints>,
+    sprites: Res<TextureAssets>,
+    fonts: Res<FontAssets>,
+) {
+    for e in preview_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+
+    let Some(lot) = queue.peek() else {
+        return;
+    };
+
+    let preview_x = map_layout
+        .hex_to_world_pos(Hex::new(map.map_radius as i32 + 4, 0))
+        .x
+        + HEX_WIDTH * 2.2;
+    let tween_in = |delay_ms: u64| {
+        Animator::new(delay_tween(
+            get_scale_tween(None, Vec3::ONE, 300, EaseFunction::BackOut),
+            delay_ms,
+        ))
+    };
+
+    cmd.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "NEXT",
+                TextStyle {
+                    font_size: 30.0,
+                    color: Color::rgb_u8(61, 51, 51),
+                    font: fonts.main.clone(),
+                },
+            ),
+            transform: Transform::from_xyz(preview_x, 190., 1.).with_scale(Vec2::ZERO.extend(1.)),
+            ..default()
+        },
+        tween_in(300),
+        LotPreview,
+        ResettableGrid,
+    ));
+
+    for (piece_i, piece_hexes) in lot.iter().enumerate() {
+        // spaced and centred like the offered lot, only smaller
+        let centre = piece_centre(&map_layout, piece_hexes.iter().map(|hex| hex.offset));
+        let y = (piece_i as f32 - 1.) * LOT_SLOT_SPACING;
+        let pos = Vec2::new(preview_x, 0.) + (Vec2::new(0., y) - centre) * PREVIEW_SCALE;
+
+        cmd.spawn((
+            SpatialBundle::from_transform(
+                Transform::from_translation(pos.extend(1.)).with_scale(Vec2::ZERO.extend(1.)),
+            ),
+            tween_in(400 + piece_i as u64 * 80),
+            LotPreview,
+            ResettableGrid,
+        ))
+        .with_children(|b| {
+            for hex in piece_hexes.iter() {
+                b.spawn(SpriteSheetBundle {
+                    transform: Transform {
+                        translation: (map_layout.hex_to_world_pos(hex.offset) * PREVIEW_SCALE)
+                            .extend(0.1),
+                        rotation: Quat::from_rotation_z((hex.rotation as f32 * 60.).to_radians()),
+                        scale: Vec2::splat(PREVIEW_SCALE).extend(1.),
+                    },
+                    sprite: TextureAtlasSprite::new(blueprints.atlas_index(hex)),
+                    texture_atlas: sprites.tiles.clone(),
+                    ..default()
+                });
+            }
+        });
+    }
+}
@@ -1,10 +1,19 @@
 use crate::{
     animation::{delay_tween, get_scale_anim, get_scale_tween},
-    loading::{MainCam, TextureAssets},
+    board::{generate_board, BoardLayout},
+    connectivity::NodeSets,
+    daily::{daily_seed, today},
+    difficulty::Difficulty,
+    game_mode::GameMode,
+    level::{LevelAsset, TestLevel},
+    loading::{LevelAssets, MainCam, TextureAssets},
+    lot_queue::LotQueue,
     map_completion::CompletedMap,
-    piece::{get_opposite_side_index, PieceHexData},
+    piece::{get_opposite_side_index, HexBlueprints, PieceHexData},
+    replay::ReplayPlayback,
     reset::ResettableGrid,
     score::Level,
+    solver::is_board_solvable,
     GameState,
 };
 use bevy::{
@@ -13,10 +22,8 @@ use bevy::{
     sprite::MaterialMesh2dBundle,
     utils::{
         petgraph::{
-            adj::NodeIndex,
-            algo::{astar, dijkstra},
-            data::Build,
-            graph::UnGraph,
+            algo::astar,
+            stable_graph::{NodeIndex, StableUnGraph},
         },
         HashMap, HashSet,
     },
@@ -25,8 +32,9 @@ use bevy::{
 use bevy_tweening::{Animator, EaseFunction};
 use hexx::{shapes, Direction, *};
 use rand::{
+    rngs::StdRng,
     seq::{IteratorRandom, SliceRandom},
-    thread_rng, Rng,
+    thread_rng, Rng, SeedableRng,
 };
 use strum::EnumIter;
 
@@ -43,14 +51,105 @@ pub const HEX_HEIGHT: f32 = HEX_SIZE * 2.;
 pub struct MapPlugin;
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Game), spawn_grid);
+        app.init_resource::<BoardSeed>()
+            .init_resource::<BoardGeneration>()
+            .add_systems(
+                OnEnter(GameState::Game),
+                (restart_seed, spawn_grid.after(restart_seed)),
+            )
+            .add_systems(
+                Update,
+                spawn_board_layout.run_if(
+                    in_state(GameState::Game)
+                        .and_then(resource_exists_and_changed::<BoardLayout>()),
+                ),
+            );
     }
 }
 
+/// Env var that pins the seed of every run (handy for reproducing bug reports).
+const SEED_ENV_VAR: &str = "BEE_TRAILS_SEED";
+
+/// Seed of the current run.
+/// The same seed, level and reroll always produce the same board and piece lots.
+#[derive(Debug, Resource, Clone, Copy, PartialEq, Eq)]
+pub struct BoardSeed {
+    pub seed: u64,
+    /// Number of times the board of the current level has been rerolled (skipped).
+    pub reroll: u32,
+}
+
+impl BoardSeed {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, reroll: 0 }
+    }
+
+    pub fn board_seed(&self, level: u32) -> u64 {
+        self.seed
+            ^ (level as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (self.reroll as u64).rotate_left(32)
+    }
+}
+
+impl Default for BoardSeed {
+    fn default() -> Self {
+        Self::new(
+            std::env::var(SEED_ENV_VAR)
+                .ok()
+                .and_then(|seed| seed.parse().ok())
+                .unwrap_or_else(|| thread_rng().gen()),
+        )
+    }
+}
+
+/// How boards are generated.
+#[derive(Debug, Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoardGeneration {
+    /// Any generated board is used.
+    Any,
+    /// Boards are rerolled until all the houses can be connected.
+    #[default]
+    Solvable,
+}
+
+/// Upper bound on rerolls so a bad level config can't hang the game.
+const MAX_SOLVABLE_REROLLS: u32 = 50;
+
+/// Rng the board and its piece lots are generated with.
+/// It's reseeded from [`BoardSeed`] every time a board is spawned.
+#[derive(Resource, Deref, DerefMut)]
+pub struct BoardRng(pub StdRng);
+
+fn restart_seed(
+    mut seed: ResMut<BoardSeed>,
+    mode: Res<GameMode>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    *seed = if let Some(playback) = playback {
+        BoardSeed::new(playback.replay.seed)
+    } else if *mode == GameMode::Daily {
+        BoardSeed::new(daily_seed(today()))
+    } else {
+        BoardSeed::default()
+    };
+    info!("Run seed: {}", seed.seed);
+}
+
 #[derive(Debug, Resource, Deref, DerefMut)]
 pub struct WorldLayout(HexLayout);
 
-type MapGraph = UnGraph<(), ()>;
+impl Default for WorldLayout {
+    fn default() -> Self {
+        Self(HexLayout {
+            hex_size: Vec2::splat(HEX_SIZE),
+            orientation: HexOrientation::Pointy,
+            ..default()
+        })
+    }
+}
+
+/// Stable, so node indices survive removing hexes.
+type MapGraph = StableUnGraph<(), ()>;
 
 mod edge_connection {
     use std::cmp::Ordering;
@@ -84,25 +183,66 @@ pub struct WorldMap {
     pub hexes: HashMap<Hex, MapHex>,
     pub map_radius: u32,
     houses: HashSet<Hex>,
+    blocked: HashSet<Hex>,
     graph: MapGraph,
     hex_nodes: HashMap<NodeIndex, Hex>,
     hex_edge_nodes: HashMap<NodeIndex, EdgeConnection>,
     edge_connection_nodes: HashMap<EdgeConnection, NodeIndex>,
+    components: NodeSets,
+    placed_pieces: u32,
+    /// Elapsed game time the board was spawned at.
+    pub started_at: f32,
 }
 
 impl WorldMap {
+    /// Builds the map of a board.
+    /// `occupant` provides the entity of each house and blocked hex.
+    pub fn new(board: &BoardLayout, mut occupant: impl FnMut(Hex) -> Entity) -> Self {
+        let mut graph = MapGraph::default();
+        let mut hexes: HashMap<Hex, MapHex> = board
+            .playable
+            .iter()
+            .map(|hex| (*hex, MapHex::empty(&mut graph)))
+            .collect();
+
+        for hex in board.houses.iter().chain(board.blocked.iter()) {
+            hexes.insert(*hex, MapHex::occupied(occupant(*hex), &mut graph));
+        }
+
+        Self {
+            houses: board.houses.iter().copied().collect(),
+            blocked: board.blocked.iter().copied().collect(),
+            hex_nodes: hexes
+                .iter()
+                .map(|(h, map_hex)| (map_hex.node_index, *h))
+                .collect(),
+            hexes,
+            graph,
+            edge_connection_nodes: HashMap::new(),
+            hex_edge_nodes: HashMap::new(),
+            components: NodeSets::default(),
+            placed_pieces: 0,
+            started_at: 0.,
+            map_radius: board.map_radius,
+        }
+    }
+
     pub fn house_count(&self) -> usize {
         self.houses.len()
     }
 
-    fn get_or_add_edge_connection(&mut self, a: Hex, b: Hex) -> u32 {
+    pub fn houses(&self) -> impl Iterator<Item = Hex> + '_ {
+        self.houses.iter().copied()
+    }
+
+    fn get_or_add_edge_connection(&mut self, a: Hex, b: Hex) -> NodeIndex {
         let edge_conn = EdgeConnection::new(a, b);
 
         *self
             .edge_connection_nodes
             .entry(edge_conn.clone())
             .or_insert_with(|| {
-                let index = self.graph.add_node(()).index() as u32;
+                let index = self.graph.add_node(());
                 self.hex_edge_nodes.insert(index, edge_conn);
 
                 index
@@ -121,47 +261,301 @@ impl WorldMap {
             let target_hex = *hex + Hex::new(1, -1).rotate_cw(side as u32);
             let edge_node = self.get_or_add_edge_connection(*hex, target_hex);
 
-            self.graph.add_edge(hex_node.into(), edge_node.into(), ());
+            self.graph.update_edge(hex_node, edge_node, ());
+            self.components.union(hex_node, edge_node);
 
             // add connections to adjacent houses
             if self.houses.contains(&target_hex) {
-                self.graph.add_edge(
-                    edge_node.into(),
-                    self.hexes[&target_hex].node_index.into(),
-                    (),
-                );
+                let house_node = self.hexes[&target_hex].node_index;
+                self.graph.update_edge(edge_node, house_node, ());
+                self.components.union(edge_node, house_node);
             }
         }
     }
 
     pub fn place_piece(&mut self, hex: Hex, piece_hexes: &HashMap<Hex, PieceHexData>) {
-        let placed_hexes: Vec<_> = piece_hexes
+        for (key, val) in piece_hexes.iter() {
+            self.place_hex(hex + *key, val.entity, val.connections);
+        }
+
+        self.placed_pieces += 1;
+    }
+
+    /// Places a single hex, `connections` being none for an empty route hex.
+    pub fn place_hex(&mut self, hex: Hex, hex_e: Entity, connections: Option<[bool; 6]>) {
+        if !self.hexes.contains_key(&hex) {
+            return;
+        }
+
+        if let Some(connected_sides) = &connections {
+            self.add_hex_graph_edges(&hex, connected_sides);
+        }
+
+        self.hexes.entry(hex).and_modify(|map_hex| {
+            map_hex.placed_hex_e = Some(hex_e);
+            map_hex.connections = connections;
+        });
+    }
+
+    /// Removes the hexes of a placed piece.
+    pub fn remove_piece(&mut self, hexes: impl IntoIterator<Item = Hex>) {
+        self.remove_hexes(hexes);
+        self.placed_pieces = self.placed_pieces.saturating_sub(1);
+    }
+
+    /// Clears placed route hexes so other pieces can go there again.
+    pub fn remove_hexes(&mut self, hexes: impl IntoIterator<Item = Hex>) {
+        for hex in hexes {
+            self.remove_hex(hex);
+        }
+    }
+
+    pub fn placed_pieces(&self) -> u32 {
+        self.placed_pieces
+    }
+
+    /// Placed hexes that carry a route.
+    pub fn route_hexes(&self) -> impl Iterator<Item = Hex> + '_ {
+        self.hexes
+            .iter()
+            .filter(|(_, map_hex)| map_hex.connections.is_some())
+            .map(|(hex, _)| *hex)
+    }
+
+    pub fn empty_hexes(&self) -> impl Iterator<Item = Hex> + '_ {
+        self.hexes
             .iter()
-            .map(|(key, val)| (hex + *key, (val.connections.clone(), val.entity.clone())))
+            .filter(|(_, map_hex)| map_hex.placed_hex_e.is_none())
+            .map(|(hex, _)| *hex)
+    }
+
+    /// Independent cycles of the route network.
+    pub fn loop_count(&self) -> usize {
+        let linked: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|n| self.graph.neighbors(*n).next().is_some())
             .collect();
+        let components: HashSet<_> = linked.iter().map(|n| self.components.find(*n)).collect();
+
+        (self.graph.edge_count() + components.len()).saturating_sub(linked.len())
+    }
+
+    /// Union-find sets can't be split, so they get rebuilt from the remaining graph edges.
+    fn rebuild_components(&mut self) {
+        self.components = NodeSets::default();
+
+        for edge in self.graph.edge_indices() {
+            if let Some((a, b)) = self.graph.edge_endpoints(edge) {
+                self.components.union(a, b);
+            }
+        }
+    }
+
+    /// Houses grouped by the routes linking them.
+    /// Groups are ordered by their first house in hex order, so the order is stable between placements.
+    pub fn house_groups(&self) -> Vec<Vec<Hex>> {
+        let mut houses: Vec<_> = self.houses.iter().copied().collect();
+        houses.sort_by_key(|hex| (hex.x, hex.y));
+
+        let mut groups: Vec<(NodeIndex, Vec<Hex>)> = Vec::new();
+
+        for house in houses {
+            let root = self.components.find(self.hexes[&house].node_index);
+
+            match groups
+                .iter_mut()
+                .find(|(group_root, _)| *group_root == root)
+            {
+                Some((_, group)) => group.push(house),
+                None => groups.push((root, vec![house])),
+            }
+        }
+
+        groups.into_iter().map(|(_, group)| group).collect()
+    }
+
+    /// Clears a placed hex and removes the edge nodes no other route hex connects to.
+    /// Returns the entity that was placed there. Houses and blocked hexes can't be removed.
+    pub fn remove_hex(&mut self, hex: Hex) -> Option<Entity> {
+        if self.houses.contains(&hex) || self.blocked.contains(&hex) {
+            return None;
+        }
+
+        let owned_edges = self.owned_edges(hex);
+        let had_edges = !owned_edges.is_empty();
+        let map_hex = self.hexes.get_mut(&hex)?;
+        let hex_node = map_hex.node_index;
+        map_hex.connections = None;
+        let placed_hex_e = map_hex.placed_hex_e.take();
+
+        for edge_conn in owned_edges {
+            let edge_node = self.edge_connection_nodes[&edge_conn];
+
+            if let Some(edge) = self.graph.find_edge(hex_node, edge_node) {
+                self.graph.remove_edge(edge);
+            }
+
+            let shared = self
+                .graph
+                .neighbors(edge_node)
+                .any(|n| !self.houses.contains(&self.hex_nodes[&n]));
+
+            if !shared {
+                self.graph.remove_node(edge_node);
+                self.hex_edge_nodes.remove(&edge_node);
+                self.edge_connection_nodes.remove(&edge_conn);
+            }
+        }
+
+        if had_edges {
+            self.rebuild_components();
+        }
+
+        placed_hex_e
+    }
+
+    /// Edges the route hex at `hex` connects to.
+    pub fn owned_edges(&self, hex: Hex) -> Vec<EdgeConnection> {
+        self.hexes.get(&hex).map_or(Vec::new(), |map_hex| {
+            self.graph
+                .neighbors(map_hex.node_index)
+                .filter_map(|n| self.hex_edge_nodes.get(&n))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Checks that the graph and the lookup maps agree with each other and with the placed hexes.
+    pub fn check_consistency(&self) -> Result<(), String> {
+        if self.graph.node_count() != self.hex_nodes.len() + self.hex_edge_nodes.len() {
+            return Err(format!(
+                "Graph has {} nodes, but there are {} hex and {} edge nodes",
+                self.graph.node_count(),
+                self.hex_nodes.len(),
+                self.hex_edge_nodes.len()
+            ));
+        }
+
+        if self.hex_nodes.len() != self.hexes.len() {
+            return Err(format!(
+                "{} hex nodes for {} hexes",
+                self.hex_nodes.len(),
+                self.hexes.len()
+            ));
+        }
 
-        // place hexes
-        for (hex, (connected_sides, hex_e)) in placed_hexes.iter() {
-            if let Some(connected_sides) = connected_sides {
-                self.add_hex_graph_edges(&hex, connected_sides);
+        for (hex, map_hex) in self.hexes.iter() {
+            if self.hex_nodes.get(&map_hex.node_index) != Some(hex) {
+                return Err(format!("Node of {hex:?} doesn't map back to it"));
             }
 
-            self.hexes.entry(*hex).and_modify(|map_hex| {
-                map_hex.placed_hex_e = Some(*hex_e);
+            if self.houses.contains(hex) {
+                continue;
+            }
+
+            let mut owned_edges = self.owned_edges(*hex);
+            let mut expected_edges: Vec<_> = map_hex.connections.map_or(Vec::new(), |sides| {
+                (0..6)
+                    .filter(|side| sides[*side])
+                    .map(|side| {
+                        EdgeConnection::new(*hex, *hex + Hex::new(1, -1).rotate_cw(side as u32))
+                    })
+                    .collect()
             });
+
+            let key = |e: &EdgeConnection| (e.first().x, e.first().y, e.second().x, e.second().y);
+            owned_edges.sort_by_key(key);
+            expected_edges.sort_by_key(key);
+
+            if owned_edges != expected_edges {
+                return Err(format!(
+                    "{hex:?} owns {owned_edges:?}, but its connections give {expected_edges:?}"
+                ));
+            }
+        }
+
+        for (edge_conn, edge_node) in self.edge_connection_nodes.iter() {
+            if self.hex_edge_nodes.get(edge_node) != Some(edge_conn) {
+                return Err(format!("Node of {edge_conn:?} doesn't map back to it"));
+            }
+
+            if !self
+                .graph
+                .neighbors(*edge_node)
+                .any(|n| !self.houses.contains(&self.hex_nodes[&n]))
+            {
+                return Err(format!("{edge_conn:?} isn't owned by any route hex"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Previews the edges of a piece placed with its origin at `hex` without touching the graph.
+    /// Also includes the placed routes leading into the piece, which it would cut off.
+    pub fn preview_edges(
+        &self,
+        hex: Hex,
+        piece_hexes: &HashMap<Hex, PieceHexData>,
+    ) -> Vec<(EdgeConnection, EdgePreview)> {
+        let placed_hexes: HashMap<_, _> = piece_hexes
+            .iter()
+            .map(|(key, val)| (hex + *key, val.connections))
+            .collect();
+        let mut edges: Vec<(EdgeConnection, EdgePreview)> = Vec::new();
+
+        for (hex, connected_sides) in placed_hexes.iter() {
+            for side in 0..6 {
+                let target_hex = *hex + Hex::new(1, -1).rotate_cw(side as u32);
+                let edge_conn = EdgeConnection::new(*hex, target_hex);
+                let routed_in = self.edge_connection_nodes.contains_key(&edge_conn);
+
+                if !connected_sides.map_or(false, |sides| sides[side]) {
+                    if routed_in {
+                        edges.push((edge_conn, EdgePreview::DeadEnd));
+                    }
+
+                    continue;
+                }
+
+                let preview = if routed_in || self.houses.contains(&target_hex) {
+                    EdgePreview::Connected
+                } else if let Some(target_sides) = placed_hexes.get(&target_hex) {
+                    if target_sides.map_or(false, |sides| sides[get_opposite_side_index(side)]) {
+                        EdgePreview::Connected
+                    } else {
+                        EdgePreview::DeadEnd
+                    }
+                } else if self
+                    .hexes
+                    .get(&target_hex)
+                    .map_or(false, |map_hex| map_hex.placed_hex_e.is_none())
+                {
+                    EdgePreview::Open
+                } else {
+                    EdgePreview::DeadEnd
+                };
+
+                // edges inside the piece are found from both of their hexes
+                if !edges.iter().any(|(e, _)| *e == edge_conn) {
+                    edges.push((edge_conn, preview));
+                }
+            }
         }
+
+        edges
     }
 
     pub fn get_completed_routes(&mut self) -> Option<CompletedMap> {
         if let Some(hex) = self.houses.iter().next() {
             let start_node = self.hexes[hex].node_index;
-            let res = dijkstra(&self.graph, start_node.into(), None, |_| 1);
 
             let other_houses: Vec<_> = self.houses.iter().filter(|h| *h != hex).cloned().collect();
 
             let all_reachable = other_houses
                 .iter()
-                .all(|h| res.contains_key(&self.hexes[h].node_index.into()));
+                .all(|h| self.components.equiv(start_node, self.hexes[h].node_index));
 
             if all_reachable {
                 info!("reached all houses reached from {hex:?}");
@@ -173,15 +567,15 @@ impl WorldMap {
                             let end = self.hexes[house].node_index;
                             let (_, path) = astar(
                                 &self.graph,
-                                start_node.into(),
-                                |n| n == end.into(),
+                                start_node,
+                                |n| n == end,
                                 |_| 1,
                                 |n| {
-                                    let node_index = n.index() as u32;
-                                    let hex =
-                                        self.hex_nodes.get(&node_index).cloned().unwrap_or_else(
-                                            || self.hex_edge_nodes[&node_index].first(),
-                                        );
+                                    let hex = self
+                                        .hex_nodes
+                                        .get(&n)
+                                        .cloned()
+                                        .unwrap_or_else(|| self.hex_edge_nodes[&n].first());
                                     house.unsigned_distance_to(hex)
                                 },
                             )
@@ -189,7 +583,7 @@ impl WorldMap {
                             info!("Path from {hex:?} to {house:?}: {path:?}");
 
                             path.iter()
-                                .map(|n| self.hex_nodes.get(&(n.index() as u32)))
+                                .map(|n| self.hex_nodes.get(n))
                                 .flatten()
                                 .cloned()
                                 .collect()
@@ -199,7 +593,7 @@ impl WorldMap {
                         .graph
                         .node_indices()
                         .filter(|n| self.graph.neighbors_undirected(*n).count() == 1)
-                        .map(|n| self.hex_edge_nodes.get(&(n.index() as u32)))
+                        .map(|n| self.hex_edge_nodes.get(&n))
                         .flatten()
                         .cloned()
                         .collect(),
@@ -213,9 +607,22 @@ impl WorldMap {
     }
 }
 
+/// How an edge of a previewed piece would end up once placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgePreview {
+    /// Joins a house, a placed route or another hex of the piece.
+    Connected,
+    /// Leads to an empty hex, so a later piece can still connect to it.
+    Open,
+    /// Can't ever be connected.
+    DeadEnd,
+}
+
 #[derive(Clone, Debug)]
 pub struct MapHex {
     pub placed_hex_e: Option<Entity>,
+    /// Connected sides of the placed route hex.
+    connections: Option<[bool; 6]>,
     node_index: NodeIndex,
 }
 
@@ -223,7 +630,8 @@ impl MapHex {
     pub fn empty(graph: &mut MapGraph) -> Self {
         Self {
             placed_hex_e: None,
-            node_index: graph.add_node(()).index() as u32,
+            connections: None,
+            node_index: graph.add_node(()),
         }
     }
 
@@ -237,262 +645,200 @@ impl MapHex {
 
 pub fn spawn_grid(
     mut cmd: Commands,
-    sprites: Res<TextureAssets>,
     completed_map: Option<Res<CompletedMap>>,
-    mut cam_q: Query<(&mut OrthographicProjection, &mut Transform), With<MainCam>>,
     lvl: Res<Level>,
+    seed: Res<BoardSeed>,
+    generation: Res<BoardGeneration>,
+    blueprints: Res<HexBlueprints>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<LevelAsset>>,
+    test_level: Option<Res<TestLevel>>,
 ) {
     if completed_map.is_some() {
         cmd.remove_resource::<CompletedMap>();
     }
 
-    let mut rng = thread_rng();
-
-    let layout = HexLayout {
-        hex_size: Vec2::splat(HEX_SIZE),
-        orientation: HexOrientation::Pointy,
-        ..default()
+    info!(
+        "Spawning board - seed: {}, level: {}, reroll: {}",
+        seed.seed, lvl.0, seed.reroll
+    );
+    let mut rng = StdRng::seed_from_u64(seed.board_seed(lvl.0));
+
+    // skipping a puzzle restarts it
+    let level = if let Some(test_level) = test_level.as_ref() {
+        Some(&test_level.0)
+    } else if *mode == GameMode::Puzzle {
+        level_assets
+            .puzzles
+            .get(lvl.0 as usize % level_assets.puzzles.len().max(1))
+            .and_then(|handle| levels.get(handle))
+    } else {
+        None
     };
+    // a broken level would panic once its hexes are drawn
+    let level = level.filter(|level| match level.validate(&blueprints) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("{e}, playing a generated board instead");
+            false
+        }
+    });
 
-    vec![
-        vec![
-            Direction::Top,
-            Direction::BottomLeft,
-            Direction::BottomRight,
-        ],
-        vec![Direction::Bottom, Direction::TopLeft, Direction::TopRight],
-    ];
-
-    let map_radius = match lvl.0 {
-        0..=1 => 2,
-        2..=4 => 3,
-        5..=7 => 4,
-        _ => 5,
-    };
+    // a fresh queue, the lots are generated from the new board rng
+    cmd.insert_resource(level.and_then(|level| level.lots()).unwrap_or_default());
 
-    let direction_group = match lvl.0 {
-        0..=1 => vec![
-            vec![Direction::Top, Direction::Bottom],
-            vec![Direction::TopLeft, Direction::BottomRight],
-            vec![Direction::BottomLeft, Direction::TopRight],
-        ]
-        .choose(&mut rng)
-        .cloned()
-        .unwrap(),
-        2..=3 => vec![
-            vec![
-                Direction::Top,
-                Direction::BottomLeft,
-                Direction::BottomRight,
-            ],
-            vec![Direction::Bottom, Direction::TopLeft, Direction::TopRight],
-        ]
-        .choose(&mut rng)
-        .cloned()
-        .unwrap(),
-        4..=5 => Direction::ALL_DIRECTIONS
-            .choose_multiple(&mut rng, 4)
-            .cloned()
-            .collect(),
-        6..=7 => Direction::ALL_DIRECTIONS
-            .choose_multiple(&mut rng, 4)
-            .chain(Direction::ALL_DIRECTIONS.choose_multiple(&mut rng, 2))
-            .cloned()
-            .collect(),
-        _ => Direction::ALL_DIRECTIONS
-            .choose_multiple(&mut rng, 5)
-            .chain(Direction::ALL_DIRECTIONS.choose_multiple(&mut rng, 5))
-            .cloned()
-            .collect(),
-    };
+    let board = if let Some(level) = level {
+        level.board()
+    } else {
+        let tier = difficulty.tier(lvl.0);
+        let mut board = generate_board(tier, &mut rng);
 
-    let mut graph = MapGraph::new_undirected();
-    let mut hexes: HashMap<Hex, MapHex> = shapes::hexagon(Hex::ZERO, map_radius)
-        .map(|hex| {
-            let pos = layout.hex_to_world_pos(hex);
-            let hex_len = hex.ulength() as u64;
-            cmd.spawn((
-                SpriteSheetBundle {
-                    transform: Transform {
-                        translation: pos.extend(0.1),
-                        scale: Vec2::ZERO.extend(1.),
-                        ..default()
-                    },
-                    sprite: TextureAtlasSprite::new(12),
-                    texture_atlas: sprites.tiles.clone(),
-                    ..default()
-                },
-                Animator::new(delay_tween(
-                    get_scale_tween(
-                        None,
-                        Vec3::ONE,
-                        350,
-                        if hex_len == map_radius as u64 {
-                            EaseFunction::BackOut
-                        } else {
-                            EaseFunction::QuadraticOut
-                        },
-                    ),
-                    hex_len * 80,
-                )),
-                ResettableGrid,
-            ))
-            // .with_children(|b| {
-            //     b.spawn(Text2dBundle {
-            //         text: Text::from_section(
-            //             format!("{},{}", hex.x, hex.y),
-            //             TextStyle {
-            //                 font_size: 17.0,
-            //                 color: Color::BLACK,
-            //                 ..default()
-            //             },
-            //         ),
-            //         transform: Transform::from_xyz(0.0, 0.0, 10.0),
-            //         ..default()
-            //     });
-            // })
-            ;
-            (hex, MapHex::empty(&mut graph))
-        })
-        .collect();
+        if *generation == BoardGeneration::Solvable {
+            let mut rerolls = 0;
 
-    // houses
-    let count = 3;
-    let mut house_hexes = HashSet::with_capacity(count);
-    let mut wedge_indices = HashSet::with_capacity(count);
-    let allow_houses_outside_grid = lvl.0 >= 1;
-
-    for dir in direction_group.iter() {
-        'wedge: loop {
-            for (i, hex) in Hex::ZERO
-                .corner_wedge(
-                    ((map_radius - 2.min(map_radius))
-                        ..=(map_radius + if allow_houses_outside_grid { 1 } else { 0 }))
-                        .rev(),
-                    *dir,
-                )
-                .enumerate()
-            {
-                if house_hexes.contains(&hex) || wedge_indices.contains(&i) {
-                    continue;
+            while !is_board_solvable(&board, &blueprints) {
+                if rerolls == MAX_SOLVABLE_REROLLS {
+                    warn!("Failed to generate a solvable board in {rerolls} rerolls");
+                    break;
                 }
 
-                if rng.gen_bool(0.25) {
-                    let tween_delay = 500 + house_hexes.len() as u64 * 80;
-                    let entity = cmd
-                        .spawn((
-                            SpriteSheetBundle {
-                                transform: Transform {
-                                    translation: layout.hex_to_world_pos(hex).extend(1.),
-                                    scale: Vec2::ZERO.extend(1.),
-                                    ..default()
-                                },
-                                sprite: TextureAtlasSprite::new(11),
-                                texture_atlas: sprites.tiles.clone(),
-                                ..default()
-                            },
-                            Animator::new(delay_tween(
-                                get_scale_tween(None, Vec3::ONE, 400, EaseFunction::BackOut),
-                                tween_delay,
-                            )),
-                            ResettableGrid,
-                        ))
-                        .id();
-
-                    hexes
-                        .entry(hex)
-                        .and_modify(|h| h.placed_hex_e = Some(entity.clone()))
-                        .or_insert_with(|| MapHex::occupied(entity, &mut graph));
-                    house_hexes.insert(hex);
-                    wedge_indices.insert(i);
-
-                    let mut neighbours = hex.all_neighbors();
-
-                    if rng.gen_bool(0.5) {
-                        neighbours.reverse();
-                    }
-
-                    for (i, neighbour) in neighbours.iter().enumerate() {
-                        if hexes.contains_key(neighbour) {
-                            continue;
-                        } else if rng.gen_bool(0.25) {
-                            break;
-                        }
-
-                        cmd.spawn((
-                            SpriteSheetBundle {
-                                transform: Transform {
-                                    translation: layout.hex_to_world_pos(*neighbour).extend(0.1),
-                                    scale: Vec2::ZERO.extend(1.),
-                                    ..default()
-                                },
-                                sprite: TextureAtlasSprite::new(12),
-                                texture_atlas: sprites.tiles.clone(),
-                                ..default()
-                            },
-                            Animator::new(delay_tween(
-                                get_scale_tween(None, Vec3::ONE, 400, EaseFunction::BackOut),
-                                tween_delay + i as u64 * 80,
-                            )),
-                            ResettableGrid,
-                        ));
-                        hexes.insert(*neighbour, MapHex::empty(&mut graph));
-                    }
-
-                    break 'wedge;
-                }
+                rerolls += 1;
+                board = generate_board(tier, &mut rng);
             }
         }
-    }
 
-    // mid island
-    let island_range = match map_radius {
-        0..=2 if lvl.0 <= 2 => None,
-        3..=4 => Some(0..=1),
-        _ => Some(0..=2),
+        board
     };
 
-    if let Some(island_range) = island_range {
-        let mut skip_count = 0;
-        let mut tween_offset_i = 0;
-        for island_hex in Hex::ZERO.spiral_range(island_range.clone()) {
-            if skip_count > 0 {
-                skip_count -= 1;
-                continue;
-            } else if rng.gen_bool(if island_range.end() == &1 {
-                0.225
-            } else {
-                0.125
-            }) {
-                skip_count = 2;
-                continue;
-            } else {
-                tween_offset_i += 1;
-            }
+    cmd.remove_resource::<WorldMap>();
+    cmd.insert_resource(board);
+    cmd.insert_resource(BoardRng(rng));
+}
+
+pub fn spawn_board_layout(
+    mut cmd: Commands,
+    board: Res<BoardLayout>,
+    time: Res<Time>,
+    sprites: Res<TextureAssets>,
+    blueprints: Res<HexBlueprints>,
+    mut cam_q: Query<(&mut OrthographicProjection, &mut Transform), With<MainCam>>,
+) {
+    let layout = WorldLayout::default();
+    let map_radius = board.map_radius;
+
+    let mut spawn_tile = |hex: Hex,
+                          atlas_index: usize,
+                          z: f32,
+                          duration_ms: u64,
+                          ease: EaseFunction,
+                          delay_ms: u64| {
+        cmd.spawn((
+            SpriteSheetBundle {
+                transform: Transform {
+                    translation: layout.hex_to_world_pos(hex).extend(z),
+                    scale: Vec2::ZERO.extend(1.),
+                    ..default()
+                },
+                sprite: TextureAtlasSprite::new(atlas_index),
+                texture_atlas: sprites.tiles.clone(),
+                ..default()
+            },
+            Animator::new(delay_tween(
+                get_scale_tween(None, Vec3::ONE, duration_ms, ease),
+                delay_ms,
+            )),
+            ResettableGrid,
+        ))
+        .id()
+    };
 
-            let entity = cmd
-                .spawn((
-                    SpriteSheetBundle {
-                        transform: Transform {
-                            translation: layout.hex_to_world_pos(island_hex).extend(1.),
-                            scale: Vec2::ZERO.extend(1.),
-                            ..default()
-                        },
-                        sprite: TextureAtlasSprite::new(10),
-                        texture_atlas: sprites.tiles.clone(),
-                        ..default()
-                    },
-                    Animator::new(delay_tween(
-                        get_scale_tween(None, Vec3::ONE, 400, EaseFunction::BackOut),
-                        300 + tween_offset_i * 80,
-                    )),
-                    ResettableGrid,
-                ))
-                .id();
-            hexes.insert(island_hex, MapHex::occupied(entity, &mut graph));
+    // empty tiles (occupied hexes inside the grid have one too)
+    let mut padding_i = 0;
+    for hex in board
+        .hexes()
+        .filter(|hex| !board.is_padding(*hex) || board.playable.contains(hex))
+    {
+        if board.is_padding(hex) {
+            spawn_tile(
+                hex,
+                12,
+                0.1,
+                400,
+                EaseFunction::BackOut,
+                500 + padding_i * 80,
+            );
+            padding_i += 1;
+        } else {
+            let hex_len = hex.ulength() as u64;
+            spawn_tile(
+                hex,
+                12,
+                0.1,
+                350,
+                if hex_len == map_radius as u64 {
+                    EaseFunction::BackOut
+                } else {
+                    EaseFunction::QuadraticOut
+                },
+                hex_len * 80,
+            );
         }
     }
 
+    let mut occupied = HashMap::with_capacity(board.houses.len() + board.blocked.len());
+
+    for (i, hex) in board.houses.iter().enumerate() {
+        let entity = spawn_tile(
+            *hex,
+            11,
+            1.,
+            400,
+            EaseFunction::BackOut,
+            500 + i as u64 * 80,
+        );
+        occupied.insert(*hex, entity);
+    }
+
+    for (i, hex) in board.blocked.iter().enumerate() {
+        let entity = spawn_tile(
+            *hex,
+            10,
+            1.,
+            400,
+            EaseFunction::BackOut,
+            300 + (i as u64 + 1) * 80,
+        );
+        occupied.insert(*hex, entity);
+    }
+
+    let routes: Vec<_> = board
+        .routes
+        .iter()
+        .enumerate()
+        .map(|(i, route)| {
+            let entity = spawn_tile(
+                route.offset,
+                blueprints.atlas_index(route),
+                1.,
+                400,
+                EaseFunction::BackOut,
+                500 + i as u64 * 80,
+            );
+
+            (route, entity)
+        })
+        .collect();
+
+    for (route, entity) in routes.iter() {
+        cmd.entity(*entity).insert(Transform {
+            translation: layout.hex_to_world_pos(route.offset).extend(1.),
+            rotation: Quat::from_rotation_z((route.rotation as f32 * 60.).to_radians()),
+            scale: Vec2::ZERO.extend(1.),
+        });
+    }
+
     // cam
     let (mut projection, mut cam_t) = cam_q.single_mut();
     projection.scale = match map_radius {
@@ -504,19 +850,13 @@ pub fn spawn_grid(
 
     cam_t.translation.x = map_radius as f32 * HEX_WIDTH;
 
-    let world_map = WorldMap {
-        houses: house_hexes.iter().map(|h| *h).collect(),
-        hex_nodes: hexes
-            .iter()
-            .map(|(h, map_hex)| (map_hex.node_index, *h))
-            .collect(),
-        hexes,
-        graph,
-        edge_connection_nodes: HashMap::new(),
-        hex_edge_nodes: HashMap::new(),
-        map_radius,
-    };
+    cmd.insert_resource(layout);
+    let mut map = WorldMap::new(&board, |hex| occupied[&hex]);
+    map.started_at = time.elapsed_seconds();
+
+    for (route, entity) in routes {
+        map.place_hex(route.offset, entity, blueprints.connections(route));
+    }
 
-    cmd.insert_resource(WorldLayout(layout));
-    cmd.insert_resource(world_map);
+    cmd.insert_resource(map);
 }
@@ -2,11 +2,16 @@ use std::time::Duration;
 
 use crate::{
     animation::{delay_tween, get_scale_anim, get_scale_tween, DespawnOnTweenCompleted},
+    audio::{PlaySfx, Sfx},
     ecs::{DelayedEvent, DelayedSystem},
+    game_mode::GameMode,
+    loading::LevelAssets,
     map::{EdgeConnection, WorldMap},
     piece::Piece,
     reset::RegisteredSystems,
-    score::{UpdateScoreEv, UpdateTimerEv},
+    score::{update_level, update_streak, Level, Streak, UpdateScoreEv, UpdateTimerEv},
+    scoring::{evaluate, DEAD_ENDS},
+    stash::Stashed,
     GameState,
 };
 use bevy::{ecs::system::SystemId, prelude::*};
@@ -19,6 +24,9 @@ impl Plugin for MapCompletionPlugin {
         app.add_systems(
             Update,
             on_map_completed
+                // the level and streak are raised once the hive is scored
+                .before(update_level)
+                .before(update_streak)
                 .run_if(in_state(GameState::Game).and_then(resource_added::<CompletedMap>())),
         );
     }
@@ -35,8 +43,22 @@ fn on_map_completed(
     map: Res<WorldMap>,
     completed_map: Res<CompletedMap>,
     systems: Res<RegisteredSystems>,
-    piece_q: Query<Entity, With<Piece>>,
+    piece_q: Query<Entity, (With<Piece>, Without<Stashed>)>,
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    level: Res<Level>,
+    level_assets: Res<LevelAssets>,
+    streak: Res<Streak>,
 ) {
+    let mut breakdown = evaluate(
+        &map,
+        &completed_map,
+        time.elapsed_seconds() - map.started_at,
+    );
+    // the streak this hive builds only multiplies the following hives
+    breakdown.multiplier = streak.multiplier();
+    let dead_ends_points = breakdown.points(DEAD_ENDS);
+
     //  despawn pieces
     for e in piece_q.iter() {
         cmd.entity(e).try_insert((
@@ -45,10 +67,10 @@ fn on_map_completed(
         ));
     }
 
-    // add routes score
+    // add routes score, dead ends get subtracted once they're shown
     cmd.spawn(DelayedEvent::new_ms(
         300,
-        UpdateScoreEv(map.house_count() as i32 * 10),
+        UpdateScoreEv(breakdown.route_points()),
     ));
 
     cmd.spawn(DelayedEvent::new_ms(
@@ -64,6 +86,12 @@ fn on_map_completed(
         // todo: raise score
 
         for (i, hex) in route.iter().enumerate() {
+            // each pop of the route a little higher
+            cmd.spawn(DelayedEvent::new_ms(
+                i as u64 * hex_stagger_ms,
+                PlaySfx::new(Sfx::HexPop).with_pitch(1. + i as f32 * 0.06),
+            ));
+
             cmd.entity(map.hexes[hex].placed_hex_e.unwrap())
                 .try_insert(Animator::new(
                     delay_tween(
@@ -104,6 +132,11 @@ fn on_map_completed(
             + 300;
 
         for (i, dead_end) in completed_map.dead_ends.iter().enumerate() {
+            cmd.spawn(DelayedEvent::new_ms(
+                deadends_delay + i as u64 * hex_stagger_ms,
+                PlaySfx::new(Sfx::DeadEnd),
+            ));
+
             for e in [dead_end.first(), dead_end.second()]
                 .iter()
                 .map(|h| map.hexes.get(h))
@@ -133,12 +166,22 @@ fn on_map_completed(
         // sub deadends score
         cmd.spawn(DelayedEvent::new_ms(
             deadends_delay + 300,
-            UpdateScoreEv(-(completed_map.dead_ends.len() as i32)),
+            UpdateScoreEv(dead_ends_points),
         ));
     }
 
+    cmd.insert_resource(breakdown);
+
+    let last_hive = mode
+        .hive_count(&level_assets)
+        .map_or(false, |hive_count| level.0 + 1 >= hive_count);
+
     cmd.spawn(DelayedSystem {
-        system_id: systems.reset,
+        system_id: if last_hive {
+            systems.end_run
+        } else {
+            systems.reset
+        },
         delay: Timer::new(Duration::from_millis(reset_delay), TimerMode::Once),
     });
 }
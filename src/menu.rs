@@ -75,7 +75,7 @@ pub fn spawn_play_btn(
 }
 
 #[derive(Component)]
-struct ChangeState(GameState);
+pub struct ChangeState(pub GameState);
 
 #[derive(Component)]
 pub struct RunSystem(pub SystemId);
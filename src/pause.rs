@@ -0,0 +1,225 @@
+use crate::{
+    ecs::Delayed,
+    input::GameAction,
+    loading::FontAssets,
+    map_completion::CompletedMap,
+    menu::{ButtonColors, RunSystem},
+    reset::{RegisteredSystems, Resettable, ResettableGrid},
+    settings::Rebinding,
+    GameState,
+};
+use bevy::{prelude::*, ui::FocusPolicy, window::WindowFocused};
+use leafwing_input_manager::prelude::*;
+
+/// Whether the run is paused.
+/// It's kept apart from [`GameState`], because leaving [`GameState::Game`] ends the run.
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum PauseState {
+    #[default]
+    Running,
+    /// The virtual clock is stopped, so the timer, tweens, cooldowns and delayed events all freeze.
+    /// Systems handling piece input don't run either.
+    Paused,
+    /// Paused with the settings open instead of the pause menu.
+    Settings,
+}
+
+/// Marks a run left from the pause menu.
+/// Abandoned runs don't count, so they aren't recorded as high scores, replays or daily attempts.
+#[derive(Debug, Resource, Default)]
+pub struct AbandonedRun;
+
+#[derive(Component)]
+struct PauseOverlay;
+
+pub struct PausePlugin;
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .add_systems(OnEnter(PauseState::Paused), spawn_overlay)
+            .add_systems(OnExit(PauseState::Paused), despawn_overlay)
+            // leaving the run for the tutorial (or the game over screen) resumes the clock
+            .add_systems(OnExit(GameState::Game), resume_game)
+            .add_systems(OnEnter(GameState::Game), clear_abandoned_run)
+            .add_systems(
+                Update,
+                (
+                    // the key being rebound might be the pause one
+                    toggle_pause.run_if(not(resource_exists::<Rebinding>())),
+                    pause_on_focus_loss.run_if(in_state(PauseState::Running)),
+                )
+                    .distributive_run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+pub fn pause_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    completed_map: Option<Res<CompletedMap>>,
+) {
+    // the completed hive is scored and reset by delayed events and systems,
+    // restarting or quitting the run halfway would leave it half done
+    if completed_map.is_some() {
+        return;
+    }
+
+    time.pause();
+    next_state.set(PauseState::Paused);
+}
+
+pub fn open_pause_settings(mut next_state: ResMut<NextState<PauseState>>) {
+    next_state.set(PauseState::Settings);
+}
+
+pub fn resume_game(mut time: ResMut<Time<Virtual>>, mut next_state: ResMut<NextState<PauseState>>) {
+    time.unpause();
+    next_state.set(PauseState::Running);
+}
+
+/// Ends the run and starts a new one in the same mode right away.
+pub fn restart_run(world: &mut World) {
+    world.insert_resource(AbandonedRun);
+    world.run_schedule(OnExit(GameState::Game));
+
+    // despawned right away instead of tweening out, so the old pieces can't be played
+    // and the old delayed events and systems don't fire into the new run
+    let mut leftovers: Vec<_> = world
+        .query_filtered::<Entity, Or<(With<Resettable>, With<ResettableGrid>)>>()
+        .iter(world)
+        .collect();
+    leftovers.extend(world.query_filtered::<Entity, Delayed>().iter(world));
+    for e in leftovers {
+        if let Some(e) = world.get_entity_mut(e) {
+            e.despawn_recursive();
+        }
+    }
+
+    world.run_schedule(OnEnter(GameState::Game));
+}
+
+/// Leaves the run for the tutorial.
+pub fn quit_run(
+    mut cmd: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    delayed_q: Query<Entity, Delayed>,
+) {
+    for e in delayed_q.iter() {
+        cmd.entity(e).despawn();
+    }
+
+    cmd.insert_resource(AbandonedRun);
+    next_state.set(GameState::Tutorial);
+}
+
+fn clear_abandoned_run(mut cmd: Commands) {
+    cmd.remove_resource::<AbandonedRun>();
+}
+
+fn toggle_pause(
+    input: Res<ActionState<GameAction>>,
+    state: Res<State<PauseState>>,
+    systems: Res<RegisteredSystems>,
+    mut cmd: Commands,
+) {
+    if input.just_pressed(GameAction::Pause) {
+        cmd.run_system(match state.get() {
+            PauseState::Running => systems.pause,
+            PauseState::Paused => systems.resume,
+            // back to the pause menu
+            PauseState::Settings => systems.pause,
+        });
+    }
+}
+
+fn pause_on_focus_loss(
+    mut ev_r: EventReader<WindowFocused>,
+    systems: Res<RegisteredSystems>,
+    mut cmd: Commands,
+) {
+    if ev_r.read().any(|ev| !ev.focused) {
+        cmd.run_system(systems.pause);
+    }
+}
+
+fn spawn_overlay(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSystems>) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::rgb_u8(61, 51, 51),
+        font: fonts.main.clone(),
+    };
+
+    // the clock is stopped, so unlike the other menus the overlay doesn't tween in
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba_u8(254, 225, 184, 220).into(),
+            // keeps the buttons of the run from being clicked
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        PauseOverlay,
+    ))
+    .with_children(|b| {
+        b.spawn(
+            TextBundle::from_section("PAUSED", text_style(90.)).with_style(Style {
+                margin: UiRect::bottom(Val::Px(40.)),
+                ..default()
+            }),
+        );
+
+        spawn_pause_btn(b, "RESUME", text_style(40.), RunSystem(systems.resume));
+        spawn_pause_btn(b, "RESTART", text_style(40.), RunSystem(systems.restart));
+        spawn_pause_btn(
+            b,
+            "SETTINGS",
+            text_style(40.),
+            RunSystem(systems.pause_settings),
+        );
+        spawn_pause_btn(b, "TUTORIAL", text_style(40.), RunSystem(systems.quit));
+    });
+}
+
+fn spawn_pause_btn(
+    children: &mut ChildBuilder,
+    label: &str,
+    text_style: TextStyle,
+    action: impl Bundle,
+) {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(220.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style));
+        });
+}
+
+fn despawn_overlay(mut cmd: Commands, overlay_q: Query<Entity, With<PauseOverlay>>) {
+    for e in overlay_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+}
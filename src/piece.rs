@@ -4,14 +4,24 @@ use crate::{
         get_relative_translation_anim, get_scale_anim, get_scale_tween, get_translation_anim,
         get_translation_tween, DespawnOnTweenCompleted,
     },
+    audio::{PlaySfx, Sfx},
     cooldown::{Cooldown, Rotating},
+    difficulty::{DifficultyConfig, DifficultyError},
+    history::{Placement, PlacementHistory},
     input::GameAction,
     loading::{MainCam, TextureAssets},
-    map::{WorldLayout, WorldMap, HEX_SIZE, HEX_SIZE_INNER, HEX_WIDTH},
+    lot_queue::LotQueue,
+    map::{
+        BoardRng, EdgeConnection, EdgePreview, WorldLayout, WorldMap, HEX_HEIGHT, HEX_SIZE,
+        HEX_SIZE_INNER, HEX_WIDTH,
+    },
     map_completion::CompletedMap,
     math::{asymptotic_smoothing, asymptotic_smoothing_with_delta_time},
     mouse::CursorPosition,
-    reset::ResettableGrid,
+    pause::PauseState,
+    reset::{Resettable, ResettableGrid},
+    score::Level,
+    stash::Stashed,
     GameState,
 };
 use bevy::{
@@ -26,6 +36,7 @@ use bevy_tweening::{Animator, EaseFunction, Tracks};
 use hexx::Hex;
 use leafwing_input_manager::prelude::*;
 use rand::{distributions::WeightedIndex, prelude::*};
+use serde::{Deserialize, Serialize};
 use std::{f32::consts::E, marker::PhantomData, ops::Add};
 use strum::IntoEnumIterator;
 
@@ -33,72 +44,278 @@ use strum::IntoEnumIterator;
 struct RouteHexBlueprint {
     connected_sides: [bool; 6],
     atlas_index: usize,
-    weight: u8,
+    /// Whether it can show up in a lot at any level.
+    spawnable: bool,
+}
+
+/// Piece weights of the levels starting at `from_level`.
+#[derive(Debug)]
+struct LevelWeights {
+    from_level: u32,
+    blueprint_index: WeightedIndex<u8>,
+    size_index: WeightedIndex<u8>,
 }
 
+/// Largest piece [`generate_piece`] can make.
+pub const MAX_PIECE_SIZE: usize = 3;
+
 #[derive(Debug, Resource)]
-struct HexBlueprints {
+pub struct HexBlueprints {
     hexes: Vec<RouteHexBlueprint>,
-    weighted_index: WeightedIndex<u8>,
-    size_weighted_index: WeightedIndex<u8>,
-}
-
-impl Default for HexBlueprints {
-    fn default() -> Self {
-        // these go clockwise from the top-right edge (pointy hexes)
-        let blueprints: Vec<_> = [
-            ([false, true, true, false, false, false], 3),
-            ([false, true, false, true, false, false], 8),
-            ([false, true, false, false, true, false], 10),
-            ([false, true, true, true, false, false], 2),
-            ([false, true, false, true, true, false], 4),
-            ([false, true, true, false, true, false], 4),
-            ([false, true, false, true, false, true], 3),
-            ([false, true, true, true, true, false], 1),
-            ([false, true, true, false, true, true], 1),
-            ([true, true, true, true, true, true], 0),
-        ]
-        .into_iter()
-        .enumerate()
-        .map(
-            |(atlas_index, (connected_sides, weight))| RouteHexBlueprint {
-                connected_sides,
-                weight,
-                atlas_index,
-            },
-        )
-        .collect();
-
-        let weighted_index = WeightedIndex::new(blueprints.iter().map(|h| h.weight)).unwrap();
+    /// Ordered by the level, the first one starting at level 0.
+    level_weights: Vec<LevelWeights>,
+}
 
-        Self {
-            hexes: blueprints,
-            weighted_index,
-            // todo: tweak when triples work properly
-            size_weighted_index: WeightedIndex::new([3, 4 /*3*/]).unwrap(),
-            // size_weighted_index: WeightedIndex::new([2, 3, 1]).unwrap(),
+impl HexBlueprints {
+    pub fn new(config: &DifficultyConfig) -> Result<Self, DifficultyError> {
+        if config.tiers.is_empty() {
+            return Err(DifficultyError::NoTiers);
         }
+
+        // the weights change at the start of every tier and blueprint weight range
+        let mut levels: Vec<_> = std::iter::once(0)
+            .chain(config.tiers.iter().map(|tier| tier.from_level))
+            .chain(
+                config
+                    .blueprints
+                    .iter()
+                    .flat_map(|bp| bp.weights.iter().map(|(from_level, _)| *from_level)),
+            )
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+
+        let level_weights = levels
+            .into_iter()
+            .map(|level| {
+                let size_weights = &config.tier(level).piece_size_weights;
+
+                if size_weights.len() > MAX_PIECE_SIZE {
+                    return Err(DifficultyError::PieceSize {
+                        level,
+                        size: size_weights.len(),
+                    });
+                }
+
+                let weights_error = |error| DifficultyError::Weights { level, error };
+
+                Ok(LevelWeights {
+                    from_level: level,
+                    blueprint_index: WeightedIndex::new(
+                        config.blueprints.iter().map(|bp| bp.weight(level)),
+                    )
+                    .map_err(weights_error)?,
+                    size_index: WeightedIndex::new(size_weights).map_err(weights_error)?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            hexes: config
+                .blueprints
+                .iter()
+                .map(|bp| RouteHexBlueprint {
+                    connected_sides: bp.connected_sides,
+                    atlas_index: bp.atlas_index,
+                    spawnable: bp.weights.iter().any(|(_, weight)| *weight > 0),
+                })
+                .collect(),
+            level_weights,
+        })
+    }
+
+    fn weights(&self, level: u32) -> &LevelWeights {
+        let i = self
+            .level_weights
+            .partition_point(|weights| weights.from_level <= level);
+
+        &self.level_weights[i.saturating_sub(1)]
+    }
+
+    /// Connected sides of the blueprints that can show up in a lot (at any level).
+    pub fn spawnable_connections(&self) -> impl Iterator<Item = &[bool; 6]> + '_ {
+        self.hexes
+            .iter()
+            .filter(|bp| bp.spawnable)
+            .map(|bp| &bp.connected_sides)
+    }
+
+    pub fn blueprint_count(&self) -> usize {
+        self.hexes.len()
+    }
+
+    pub fn atlas_index(&self, hex: &PieceHexSpec) -> usize {
+        hex.blueprint.map_or(
+            10, // empty hex index
+            |bp| self.hexes[bp].atlas_index,
+        )
+    }
+
+    /// Connected sides of the hex with its rotation applied.
+    pub fn connections(&self, hex: &PieceHexSpec) -> Option<[bool; 6]> {
+        hex.blueprint.map(|bp| {
+            let mut connected_sides = self.hexes[bp].connected_sides;
+            connected_sides.rotate_left(hex.rotation);
+            connected_sides
+        })
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Piece {
     hexes: HashMap<Hex, PieceHexData>,
     target_hex: Option<Hex>,
 }
 
+impl Piece {
+    /// Hexes of the piece relative to its origin.
+    pub fn hexes(&self) -> impl Iterator<Item = (&Hex, &PieceHexData)> {
+        self.hexes.iter()
+    }
+
+    pub fn target_hex(&self) -> Option<Hex> {
+        self.target_hex
+    }
+
+    /// Whether all the piece hexes would land on empty map hexes with the origin at `hex`.
+    pub fn fits(&self, map: &WorldMap, hex: Hex) -> bool {
+        self.hexes.keys().all(|h| {
+            map.hexes
+                .get(&(hex + *h))
+                .map_or(false, |map_hex| map_hex.placed_hex_e.is_none())
+        })
+    }
+
+    /// Snaps the piece to `hex` if it fits there.
+    pub fn try_target(&mut self, map: &WorldMap, hex: Hex) -> bool {
+        if self.fits(map, hex) {
+            self.target_hex = Some(hex);
+            true
+        } else {
+            self.target_hex.take();
+            false
+        }
+    }
+
+    /// Previews the route edges of the piece with its origin at `hex`.
+    pub fn preview_edges(&self, map: &WorldMap, hex: Hex) -> Vec<(EdgeConnection, EdgePreview)> {
+        map.preview_edges(hex, &self.hexes)
+    }
+}
+
 #[derive(Component)]
 pub struct PlacedPiece;
 
+/// Piece that's being dragged or held by the hex cursor.
 #[derive(Component)]
+pub struct Carried;
+
+/// Spawn order of the piece in the current run.
+/// Unlike the entity or the lot slot it's the same every time the run is played, so replays refer to pieces by it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PieceId(pub u32);
+
+/// Id of the next spawned piece.
+#[derive(Resource, Default)]
+struct NextPieceId(u32);
+
+/// Position of the piece in the offered lot, going from the bottom.
+#[derive(Component, Debug, Clone, Copy, Deref)]
+pub struct LotSlot(pub usize);
+
+/// Distance between the lot slots, the tallest piece (a slanted line of three hexes) fits in one.
+pub const LOT_SLOT_SPACING: f32 = HEX_SIZE * 3. + HEX_HEIGHT;
+
+/// Centre of the bounding box of the piece hexes, relative to the piece origin.
+pub fn piece_centre(map_layout: &WorldLayout, offsets: impl IntoIterator<Item = Hex>) -> Vec2 {
+    let (min, max) = offsets
+        .into_iter()
+        .map(|offset| map_layout.hex_to_world_pos(offset))
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), pos| {
+            (min.min(pos), max.max(pos))
+        });
+
+    if min.x > max.x {
+        Vec2::ZERO
+    } else {
+        (min + max) / 2.
+    }
+}
+
+/// Lot position of a piece with its hexes centred on the slot.
+pub fn lot_position(
+    map_layout: &WorldLayout,
+    map: &WorldMap,
+    slot: usize,
+    offsets: impl IntoIterator<Item = Hex>,
+) -> Vec3 {
+    let slot_pos = Vec2::new(
+        map_layout
+            .hex_to_world_pos(Hex::new(map.map_radius as i32 + 4, 0))
+            .x,
+        (slot as f32 - 1.) * LOT_SLOT_SPACING,
+    );
+
+    (slot_pos - piece_centre(map_layout, offsets)).extend(1.)
+}
+
+/// Places the piece with its origin at `hex` if all of its hexes fit on the map.
+/// Otherwise the piece returns to its lot position.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct PlacePieceRequest {
+    pub piece: Entity,
+    pub hex: Hex,
+}
+
+/// Rotates the piece around its `pivot` hex (relative to the piece origin).
+#[derive(Debug, Event, Clone, Copy)]
+pub struct RotatePieceRequest {
+    pub piece: Entity,
+    pub pivot: Hex,
+    pub clockwise: bool,
+}
+
+#[derive(Component, Clone)]
 pub struct PieceHexData {
     pub entity: Entity,
     side_index: u8,
     pub connections: Option<[bool; 6]>,
 }
 
+impl PieceHexData {
+    /// Hex data of an unrotated hex.
+    pub fn new(entity: Entity, connections: Option<[bool; 6]>) -> Self {
+        Self {
+            entity,
+            side_index: 0,
+            connections,
+        }
+    }
+}
+
 #[derive(Component, Deref, DerefMut)]
-struct InitialPosition(Vec3);
+pub struct InitialPosition(pub Vec3);
+
+/// Lot piece taken out of play, but kept around so it can be offered again.
+#[derive(Component)]
+pub struct HiddenPiece(pub Piece);
+
+pub fn hide_piece(cmd: &mut Commands, e: Entity, piece: &Piece) {
+    cmd.entity(e).remove::<(Piece, Carried)>().try_insert((
+        HiddenPiece(piece.clone()),
+        get_scale_anim(None, Vec3::ZERO, 300, EaseFunction::BackIn),
+    ));
+}
+
+pub fn show_piece(cmd: &mut Commands, e: Entity, piece: &Piece) {
+    let mut piece = piece.clone();
+    piece.target_hex = None;
+
+    cmd.entity(e).remove::<HiddenPiece>().try_insert((
+        piece,
+        get_scale_anim(None, Vec3::ONE, 300, EaseFunction::BackOut),
+    ));
+}
 
 #[derive(Component)]
 struct Dragged(Drag);
@@ -106,9 +323,11 @@ struct Dragged(Drag);
 pub struct PiecePlugin;
 impl Plugin for PiecePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<HexBlueprints>()
-            .init_resource::<HoveredPiece>()
-            .add_plugins(DefaultPickingPlugins)
+        app.init_resource::<HoveredPiece>()
+            .init_resource::<NextPieceId>()
+            .add_event::<PlacePieceRequest>()
+            .add_event::<RotatePieceRequest>()
+            .add_systems(OnEnter(GameState::Game), restart_piece_ids)
             .add_systems(
                 Update,
                 (
@@ -116,91 +335,166 @@ impl Plugin for PiecePlugin {
                     spawn_pieces,
                     drag_piece,
                     drag_piece_end.after(spawn_pieces),
+                    place_pieces.after(drag_piece_end),
                     rotate_piece,
+                    rotate_pieces.after(rotate_piece),
                     over_piece.after(out_piece),
                     out_piece,
                 )
                     .distributive_run_if(
                         in_state(GameState::Game)
                             .and_then(resource_exists::<WorldMap>())
+                            .and_then(in_state(PauseState::Running))
                             .and_then(not(resource_exists::<CompletedMap>())),
                     ),
             );
     }
 }
 
+/// Hex of a piece that's yet to be spawned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceHexSpec {
+    /// Offset from the piece origin.
+    pub offset: Hex,
+    /// Index into [`HexBlueprints`], `None` for an empty hex.
+    pub blueprint: Option<usize>,
+    /// Sides the blueprint is rotated by.
+    pub rotation: usize,
+}
+
+/// Side of the `from` hex facing its neighbour `to`.
+fn neighbour_side(from: Hex, to: Hex) -> Option<usize> {
+    (0..6).find(|side| from + Hex::new(1, -1).rotate_cw(*side as u32) == to)
+}
+
+/// Generates a piece of up to [`MAX_PIECE_SIZE`] hexes.
+/// Three hexes form either a line or a triangle and adjacent hexes always agree on their shared edge.
+/// Only a hex with a blueprint gets another hex added, so the size weights are an upper bound.
+pub fn generate_piece(
+    blueprints: &HexBlueprints,
+    level: u32,
+    rng: &mut impl Rng,
+) -> Vec<PieceHexSpec> {
+    let weights = blueprints.weights(level);
+    let size = weights.size_index.sample(rng) + 1;
+    let mut hexes: Vec<PieceHexSpec> = Vec::with_capacity(size);
+    let side_connected = |hex: &PieceHexSpec, side: usize| {
+        blueprints
+            .connections(hex)
+            .map_or(false, |connected_sides| connected_sides[side])
+    };
+
+    for size_i in 0..size {
+        let mut blueprint = Some(weights.blueprint_index.sample(rng));
+        // randomize rotation
+        let rotation = (0..6).choose(rng).unwrap();
+        let mut offset = Hex::ZERO;
+
+        if size_i > 0 {
+            let last = *hexes.last().unwrap();
+            // an empty hex has no sides to continue from, so the piece ends there
+            if blueprints.connections(&last).is_none() {
+                break;
+            }
+
+            let mut connected = false;
+
+            if rng.gen_bool(0.65) {
+                blueprint.take();
+            } else {
+                connected = rng.gen_bool(0.75);
+            }
+
+            let new_hex = PieceHexSpec {
+                offset,
+                blueprint,
+                rotation,
+            };
+            // side of the first hex the second one is on,
+            // the third hex has to continue the line or close the triangle
+            let shape_side = if size_i == 2 {
+                neighbour_side(hexes[0].offset, last.offset)
+            } else {
+                None
+            };
+
+            let side = (0..6)
+                .filter(|side| {
+                    let side_offset = last.offset + Hex::new(1, -1).rotate_cw(*side as u32);
+
+                    shape_side.map_or(true, |shape_side| (*side + 6 - shape_side) % 2 == 0)
+                        && hexes.iter().all(|hex| hex.offset != side_offset)
+                        && side_connected(&last, *side) == connected
+                        && hexes.iter().all(|hex| {
+                            neighbour_side(hex.offset, side_offset).map_or(true, |hex_side| {
+                                side_connected(hex, hex_side)
+                                    == side_connected(&new_hex, get_opposite_side_index(hex_side))
+                            })
+                        })
+                })
+                .choose(rng);
+
+            match side {
+                Some(side) => {
+                    offset = last.offset + Hex::new(1, -1).rotate_cw(side as u32);
+                }
+                None => break,
+            };
+        }
+
+        hexes.push(PieceHexSpec {
+            offset,
+            blueprint,
+            rotation,
+        });
+    }
+
+    // lines are centred on their middle hex, so no piece reaches further than a hex from its origin
+    let line_centre = match hexes.as_slice() {
+        [first, middle, last] if last.offset - middle.offset == middle.offset - first.offset => {
+            Some(middle.offset)
+        }
+        _ => None,
+    };
+
+    if let Some(centre) = line_centre {
+        for hex in hexes.iter_mut() {
+            hex.offset = hex.offset - centre;
+        }
+    }
+
+    hexes
+}
+
+fn restart_piece_ids(mut next_id: ResMut<NextPieceId>) {
+    next_id.0 = 0;
+}
+
 fn spawn_pieces(
     mut cmd: Commands,
     map_layout: Res<WorldLayout>,
     map: Res<WorldMap>,
     blueprints: Res<HexBlueprints>,
-    piece_q: Query<&Piece>,
+    lvl: Res<Level>,
+    mut board_rng: ResMut<BoardRng>,
+    mut lot_queue: ResMut<LotQueue>,
+    mut next_id: ResMut<NextPieceId>,
+    piece_q: Query<&Piece, Without<Stashed>>,
     placed_piece_q: Query<(), With<PlacedPiece>>,
     sprites: Res<TextureAssets>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
     if piece_q.iter().len() < 1 {
-        let mut rng = thread_rng();
+        let Some(lot) = lot_queue.pop(&blueprints, lvl.0, &mut board_rng.0) else {
+            return;
+        };
         let piece_tween_delay = if placed_piece_q.is_empty() { 950 } else { 200 };
-        let piece_x = map_layout
-            .hex_to_world_pos(Hex::new(map.map_radius as i32 + 4, 0))
-            .x;
 
-        for (piece_i, y) in [-220., 0., 220.].iter().enumerate() {
-            let size = blueprints.size_weighted_index.sample(&mut rng) + 1;
-            let mut hexes = HashMap::with_capacity(3);
+        for (piece_i, piece_hexes) in lot.iter().enumerate() {
+            let mut hexes = HashMap::with_capacity(piece_hexes.len());
 
-            for size_i in 0..size {
-                let mut blueprint =
-                    (&blueprints.hexes[blueprints.weighted_index.sample(&mut rng)]).clone();
-
-                // randomize rotation
-                let rotation_side = (0..6).choose(&mut rng).unwrap();
-                if rotation_side > 0 {
-                    blueprint.connected_sides.rotate_left(rotation_side);
-                }
-
-                let mut blueprint = Some(&blueprint);
-                let mut hex = Hex::ZERO;
-
-                if size_i > 0 {
-                    let prev: &PieceHexData = hexes.values().last().unwrap();
-                    let mut connected = false;
-
-                    if rng.gen_bool(0.65) {
-                        blueprint.take();
-                    } else {
-                        connected = rng.gen_bool(0.75);
-                    }
-
-                    if size_i == 1 {
-                        let side = prev.connections.map_or(None, |connected_sides| {
-                            connected_sides
-                                .iter()
-                                .enumerate()
-                                .filter(|(side_index, conn)| {
-                                    **conn == connected
-                                        && blueprint.map_or(true, |bp| {
-                                            bp.connected_sides[get_opposite_side_index(*side_index)]
-                                                == connected
-                                        })
-                                })
-                                .map(|(side, _)| side)
-                                .choose(&mut rng)
-                        });
-
-                        match side {
-                            Some(side) => {
-                                hex = Hex::new(1, -1).rotate_cw(side as u32);
-                            }
-                            None => break,
-                        };
-                    } else {
-                        panic!("Size {size_i} is invalid");
-                    }
-                }
-
-                let pos = map_layout.hex_to_world_pos(hex).extend(0.1);
+            for hex in piece_hexes.iter() {
+                let pos = map_layout.hex_to_world_pos(hex.offset).extend(0.1);
 
                 let entity = cmd
                     .spawn((
@@ -208,14 +502,11 @@ fn spawn_pieces(
                             transform: Transform {
                                 translation: pos,
                                 rotation: Quat::from_rotation_z(
-                                    (rotation_side as f32 * 60.).to_radians(),
+                                    (hex.rotation as f32 * 60.).to_radians(),
                                 ),
                                 ..default()
                             },
-                            sprite: TextureAtlasSprite::new(blueprint.map_or(
-                                10, // empty hex index
-                                |h| h.atlas_index,
-                            )),
+                            sprite: TextureAtlasSprite::new(blueprints.atlas_index(hex)),
                             texture_atlas: sprites.tiles.clone(),
                             ..default()
                         },
@@ -224,35 +515,26 @@ fn spawn_pieces(
                         ),
                         PickableBundle::default(),
                     ))
-                    // .with_children(|b| {
-                    //     b.spawn(Text2dBundle {
-                    //         text: Text::from_section(
-                    //             format!("{},{}", hex.x, hex.y),
-                    //             TextStyle {
-                    //                 font_size: 30.0,
-                    //                 color: Color::WHITE,
-                    //                 ..default()
-                    //             },
-                    //         ),
-                    //         transform: Transform::from_xyz(0.0, 0.0, 10.0),
-                    //         ..default()
-                    //     });
-                    // })
                     .id();
 
                 hexes.insert(
-                    hex,
+                    hex.offset,
                     PieceHexData {
                         entity,
-                        side_index: rotation_side as u8,
-                        connections: blueprint.map_or(None, |bp| Some(bp.connected_sides.clone())),
+                        side_index: hex.rotation as u8,
+                        connections: blueprints.connections(hex),
                     },
                 );
             }
 
             let children: Vec<_> = hexes.values().map(|d| d.entity).collect();
 
-            let pos = Vec3::new(piece_x, *y, 1.);
+            let pos = lot_position(
+                &map_layout,
+                &map,
+                piece_i,
+                piece_hexes.iter().map(|hex| hex.offset),
+            );
             cmd.spawn(SpatialBundle::from_transform(
                 Transform::from_translation(pos).with_scale(Vec2::ZERO.extend(1.)),
             ))
@@ -262,6 +544,8 @@ fn spawn_pieces(
                     target_hex: None,
                 },
                 InitialPosition(pos),
+                LotSlot(piece_i),
+                PieceId(next_id.0),
                 Animator::new(delay_tween(
                     get_scale_tween(None, Vec3::ONE, 300, EaseFunction::BackOut),
                     piece_tween_delay + piece_i as u64 * 80,
@@ -269,6 +553,8 @@ fn spawn_pieces(
                 ResettableGrid,
             ))
             .push_children(&children);
+
+            next_id.0 += 1;
         }
     }
 }
@@ -311,6 +597,8 @@ fn drag_piece(
     for (target, drag) in to_process {
         if let Ok((parent, target_t)) = target_q.get(target) {
             if let Ok((mut piece_t, initial_pos, mut piece)) = piece_q.get_mut(parent.get()) {
+                cmd.entity(parent.get()).try_insert(Carried);
+
                 let target_hex =
                     map_layout.world_pos_to_hex(cursor_pos.0 - target_t.translation.truncate());
 
@@ -320,13 +608,7 @@ fn drag_piece(
                     }
                 }
 
-                if piece.hexes.keys().all(|h| {
-                    map.hexes
-                        .get(&(target_hex + *h))
-                        .map_or(false, |map_hex| map_hex.placed_hex_e.is_none())
-                }) {
-                    piece.target_hex = Some(target_hex);
-
+                if piece.try_target(&map, target_hex) {
                     cmd.entity(parent.get()).try_insert(get_translation_anim(
                         None,
                         map_layout
@@ -336,7 +618,6 @@ fn drag_piece(
                         EaseFunction::QuadraticOut,
                     ));
                 } else {
-                    piece.target_hex.take();
                     piece_t.translation.x = initial_pos.x + drag.distance.x * projection.scale;
                     piece_t.translation.y = initial_pos.y - drag.distance.y * projection.scale;
                 }
@@ -348,77 +629,176 @@ fn drag_piece(
 fn drag_piece_end(
     mut cmd: Commands,
     mut ev_r: EventReader<Pointer<DragEnd>>,
+    mut ev_w: EventWriter<PlacePieceRequest>,
+    mut sfx_w: EventWriter<PlaySfx>,
     parent_q: Query<&Parent>,
+    piece_q: Query<(&InitialPosition, &Piece)>,
+) {
+    for ev in ev_r.read() {
+        if let Ok(parent) = parent_q.get(ev.target) {
+            if let Ok((initial_pos, piece)) = piece_q.get(parent.get()) {
+                if let Some(hex) = piece.target_hex {
+                    ev_w.send(PlacePieceRequest {
+                        piece: parent.get(),
+                        hex,
+                    });
+                } else {
+                    // the piece never snapped to the board, so it goes back to the lot
+                    sfx_w.send(PlaySfx::new(Sfx::InvalidDrop));
+                    cmd.entity(parent.get())
+                        .remove::<Carried>()
+                        .try_insert(get_translation_anim(
+                            None,
+                            initial_pos.0,
+                            250,
+                            EaseFunction::QuadraticOut,
+                        ));
+                }
+            }
+        }
+    }
+}
+
+fn place_pieces(
+    mut cmd: Commands,
+    mut ev_r: EventReader<PlacePieceRequest>,
     children_q: Query<&Children>,
-    mut piece_q: Query<(Entity, &Transform, &mut InitialPosition, &Piece)>,
+    mut piece_q: Query<(
+        Entity,
+        &Transform,
+        &mut InitialPosition,
+        &Piece,
+        Has<Stashed>,
+    )>,
+    hidden_q: Query<&HiddenPiece>,
     mut map: ResMut<WorldMap>,
+    mut history: ResMut<PlacementHistory>,
     map_layout: Res<WorldLayout>,
+    mut sfx_w: EventWriter<PlaySfx>,
 ) {
-    let mut placed_piece = None;
+    let mut placed_pieces = Vec::new();
+    let mut next_lot = Vec::new();
 
     for ev in ev_r.read() {
-        if let Ok(parent) = parent_q.get(ev.target) {
-            if let Ok((_, t, mut initial_pos, piece)) = piece_q.get_mut(parent.get()) {
-                if let Some(hex) = piece.target_hex {
-                    initial_pos.0 = map_layout.hex_to_world_pos(hex).extend(t.translation.z);
-                    cmd.entity(parent.get())
-                        .remove::<Piece>()
-                        .try_insert(PlacedPiece);
+        if placed_pieces.contains(&ev.piece) {
+            continue;
+        }
 
-                    // stop hexes from being pickable
-                    if let Ok(children) = children_q.get(parent.get()) {
-                        for child in children.iter() {
-                            cmd.entity(*child).try_insert(Pickable::IGNORE);
-                        }
-                    }
+        if let Ok((_, t, mut initial_pos, piece, stashed)) = piece_q.get_mut(ev.piece) {
+            cmd.entity(ev.piece).remove::<Carried>();
 
-                    // place hexes
-                    map.place_piece(hex, &piece.hexes);
+            if piece.fits(&map, ev.hex) {
+                sfx_w.send(PlaySfx::new(Sfx::Snap));
 
-                    if let Some(completed_map) = map.get_completed_routes() {
-                        cmd.insert_resource(completed_map);
+                let redo = history
+                    .redo
+                    .last()
+                    .map_or(false, |p| p.piece == ev.piece && p.hex == ev.hex);
 
-                        return;
+                if redo {
+                    next_lot = history.redo.pop().unwrap().next_lot;
+                } else {
+                    // the lots hidden by the undone placements can't be offered anymore
+                    for placement in history.redo.drain(..) {
+                        for e in placement.next_lot {
+                            if hidden_q.contains(e) {
+                                cmd.entity(e).despawn_recursive();
+                            }
+                        }
                     }
+                }
 
-                    // remove piece to spawn new ones
-                    placed_piece = Some(parent.get());
-                } else {
-                    cmd.entity(parent.get()).try_insert(get_translation_anim(
-                        None,
-                        initial_pos.0,
-                        250,
-                        EaseFunction::QuadraticOut,
+                let mut data = piece.clone();
+                data.target_hex = None;
+                history.undo.push(Placement {
+                    piece: ev.piece,
+                    hex: ev.hex,
+                    data,
+                    lot_position: initial_pos.0,
+                    discarded: Vec::new(),
+                    next_lot: Vec::new(),
+                    from_stash: stashed,
+                });
+
+                initial_pos.0 = map_layout.hex_to_world_pos(ev.hex).extend(t.translation.z);
+                cmd.entity(ev.piece)
+                    .remove::<(Piece, Stashed, Resettable)>()
+                    .try_insert((
+                        PlacedPiece,
+                        ResettableGrid,
+                        get_translation_anim(None, initial_pos.0, 120, EaseFunction::QuadraticOut),
                     ));
+
+                // stop hexes from being pickable
+                if let Ok(children) = children_q.get(ev.piece) {
+                    for child in children.iter() {
+                        cmd.entity(*child).try_insert(Pickable::IGNORE);
+                    }
+                }
+
+                // place hexes
+                map.place_piece(ev.hex, &piece.hexes);
+
+                if let Some(completed_map) = map.get_completed_routes() {
+                    cmd.insert_resource(completed_map);
+
+                    return;
                 }
+
+                // remove piece to spawn new ones
+                placed_pieces.push(ev.piece);
+            } else {
+                sfx_w.send(PlaySfx::new(Sfx::InvalidDrop));
+                cmd.entity(ev.piece).try_insert(get_translation_anim(
+                    None,
+                    initial_pos.0,
+                    250,
+                    EaseFunction::QuadraticOut,
+                ));
             }
         }
     }
 
-    if let Some(placed_e) = placed_piece {
+    // placing the stashed piece doesn't use up the lot
+    let lot_placed = placed_pieces
+        .iter()
+        .any(|e| piece_q.get(*e).map_or(false, |(.., stashed)| !stashed));
+
+    if lot_placed {
+        let lot_left: Vec<_> = piece_q
+            .iter()
+            .filter(|(e, .., stashed)| !stashed && !placed_pieces.contains(e))
+            .collect();
+
         // only remove last piece
-        if piece_q.iter().len() <= 2 {
-            for (e, ..) in piece_q.iter() {
-                if e == placed_e {
-                    continue;
+        if lot_left.len() <= 1 {
+            for (e, _, _, piece, _) in lot_left {
+                // hidden instead of despawned so undoing the placement can offer it again
+                hide_piece(&mut cmd, e, piece);
+
+                if let Some(placement) = history.undo.last_mut() {
+                    placement.discarded.push(e);
                 }
+            }
 
-                cmd.entity(e).try_insert((
-                    get_scale_anim(None, Vec3::ZERO, 300, EaseFunction::BackIn),
-                    DespawnOnTweenCompleted,
-                ));
+            // a redo offers the lot it got offered the first time instead of spawning a new one
+            for e in next_lot {
+                if let Ok(hidden) = hidden_q.get(e) {
+                    show_piece(&mut cmd, e, &hidden.0);
+                }
             }
         }
     }
 }
 
-struct HoveredPieceEntities {
-    piece_e: Entity,
-    hex_e: Entity,
+pub struct HoveredPieceEntities {
+    pub piece_e: Entity,
+    pub hex_e: Entity,
 }
 
+/// Piece under the mouse cursor.
 #[derive(Resource, Default, Deref, DerefMut)]
-struct HoveredPiece(Option<HoveredPieceEntities>);
+pub struct HoveredPiece(pub Option<HoveredPieceEntities>);
 
 fn over_piece(
     mut ev_r: EventReader<Pointer<Over>>,
@@ -462,10 +842,9 @@ fn out_piece(
 }
 
 fn rotate_piece(
-    mut cmd: Commands,
-    mut piece_q: Query<&mut Piece, Without<Cooldown<Rotating>>>,
+    mut ev_w: EventWriter<RotatePieceRequest>,
+    piece_q: Query<&Piece>,
     hovered: Res<HoveredPiece>,
-    map_layout: Res<WorldLayout>,
     input: Res<ActionState<GameAction>>,
 ) {
     let mut rotate_cw = None;
@@ -478,62 +857,98 @@ fn rotate_piece(
 
     if let Some(clockwise) = rotate_cw {
         if let Some(hovered) = &hovered.0 {
-            if let Ok(mut piece) = piece_q.get_mut(hovered.piece_e) {
-                let center_hex = *piece
+            if let Ok(piece) = piece_q.get(hovered.piece_e) {
+                if let Some((center_hex, _)) = piece
                     .hexes
                     .iter()
                     .find(|(_, data)| data.entity == hovered.hex_e)
-                    .unwrap()
-                    .0;
+                {
+                    ev_w.send(RotatePieceRequest {
+                        piece: hovered.piece_e,
+                        pivot: *center_hex,
+                        clockwise,
+                    });
+                }
+            }
+        }
+    }
+}
 
-                piece.hexes = piece
-                    .hexes
-                    .drain()
-                    .map(|(hex, mut piece_hex_data)| {
-                        let rotated_hex = if clockwise {
-                            hex.cw_around(center_hex)
-                        } else {
-                            hex.ccw_around(center_hex)
-                        };
-
-                        piece_hex_data.side_index = get_side_index(
-                            piece_hex_data.side_index as i8 + (if clockwise { -1 } else { 1 }),
-                        ) as u8;
-
-                        if let Some(connections) = &mut piece_hex_data.connections {
-                            if clockwise {
-                                connections.rotate_right(1);
-                            } else {
-                                connections.rotate_left(1);
-                            }
-                        }
+fn rotate_pieces(
+    mut cmd: Commands,
+    mut ev_r: EventReader<RotatePieceRequest>,
+    mut piece_q: Query<&mut Piece, Without<Cooldown<Rotating>>>,
+    map_layout: Res<WorldLayout>,
+    mut sfx_w: EventWriter<PlaySfx>,
+) {
+    let mut rotated_pieces = Vec::new();
 
-                        cmd.entity(piece_hex_data.entity).try_insert((
-                            Animator::new(Tracks::new([
-                                get_translation_tween(
-                                    None,
-                                    map_layout.hex_to_world_pos(rotated_hex).extend(0.),
-                                    350,
-                                    EaseFunction::BackInOut,
-                                ),
-                                get_relative_rotation_tween(
-                                    Quat::from_rotation_z(
-                                        (piece_hex_data.side_index as f32 * 60.).to_radians(),
-                                    ),
-                                    300,
-                                ),
-                            ])),
-                            Cooldown::<Rotating>::new(300),
-                        ));
+    for ev in ev_r.read() {
+        if rotated_pieces.contains(&ev.piece) {
+            continue;
+        }
 
-                        (rotated_hex, piece_hex_data)
-                    })
-                    .collect();
+        if let Ok(mut piece) = piece_q.get_mut(ev.piece) {
+            rotate_piece_hexes(&mut cmd, &mut piece, ev.pivot, ev.clockwise, &map_layout);
 
-                piece.target_hex.take();
-                cmd.entity(hovered.piece_e)
-                    .try_insert(Cooldown::<Rotating>::new(300));
-            }
+            piece.target_hex.take();
+            cmd.entity(ev.piece)
+                .try_insert(Cooldown::<Rotating>::new(300));
+            sfx_w.send(PlaySfx::new(Sfx::Rotate));
+            rotated_pieces.push(ev.piece);
         }
     }
 }
+
+/// Rotates the piece hexes around the `pivot` hex (relative to the piece origin).
+fn rotate_piece_hexes(
+    cmd: &mut Commands,
+    piece: &mut Piece,
+    pivot: Hex,
+    clockwise: bool,
+    map_layout: &WorldLayout,
+) {
+    piece.hexes = piece
+        .hexes
+        .drain()
+        .map(|(hex, mut piece_hex_data)| {
+            let rotated_hex = if clockwise {
+                hex.cw_around(pivot)
+            } else {
+                hex.ccw_around(pivot)
+            };
+
+            piece_hex_data.side_index =
+                get_side_index(piece_hex_data.side_index as i8 + (if clockwise { -1 } else { 1 }))
+                    as u8;
+
+            if let Some(connections) = &mut piece_hex_data.connections {
+                if clockwise {
+                    connections.rotate_right(1);
+                } else {
+                    connections.rotate_left(1);
+                }
+            }
+
+            cmd.entity(piece_hex_data.entity).try_insert((
+                Animator::new(Tracks::new([
+                    get_translation_tween(
+                        None,
+                        map_layout.hex_to_world_pos(rotated_hex).extend(0.),
+                        350,
+                        EaseFunction::BackInOut,
+                    ),
+                    get_relative_rotation_tween(
+                        Quat::from_rotation_z(
+                            (piece_hex_data.side_index as f32 * 60.).to_radians(),
+                        ),
+                        300,
+                    ),
+                ])),
+                Cooldown::<Rotating>::new(300),
+            ));
+
+            (rotated_hex, piece_hex_data)
+        })
+        .collect();
+}
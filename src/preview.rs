@@ -0,0 +1,151 @@
+use crate::{
+    loading::TextureAssets,
+    map::{EdgePreview, WorldLayout, WorldMap},
+    map_completion::CompletedMap,
+    piece::{Carried, Piece},
+    reset::ResettableGrid,
+    settings::{Palette, Settings},
+    GameState,
+};
+use bevy::prelude::*;
+use hexx::Hex;
+
+const EMPTY_TILE_INDEX: usize = 12;
+const HEX_Z: f32 = 11.;
+const EDGE_Z: f32 = 12.;
+
+/// Highlights the hexes and route edges a carried piece would end up on.
+pub struct PreviewPlugin;
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                update_preview.run_if(
+                    in_state(GameState::Game)
+                        .and_then(resource_exists::<WorldMap>())
+                        .and_then(not(resource_exists::<CompletedMap>())),
+                ),
+                clear_preview.run_if(resource_added::<CompletedMap>()),
+            ),
+        );
+    }
+}
+
+#[derive(Component)]
+struct PlacementPreview;
+
+#[derive(PartialEq)]
+struct PreviewKey {
+    piece: Entity,
+    hex: Hex,
+    hexes: Vec<(Hex, Option<[bool; 6]>)>,
+}
+
+fn edge_color(preview: EdgePreview, palette: &Palette) -> Color {
+    match preview {
+        EdgePreview::Connected => palette.connected,
+        EdgePreview::Open => palette.open,
+        EdgePreview::DeadEnd => palette.dead_end,
+    }
+}
+
+fn update_preview(
+    mut cmd: Commands,
+    carried_q: Query<(Entity, &Transform, &Piece), With<Carried>>,
+    preview_q: Query<Entity, With<PlacementPreview>>,
+    map: Res<WorldMap>,
+    map_layout: Res<WorldLayout>,
+    sprites: Res<TextureAssets>,
+    settings: Res<Settings>,
+    mut last_key: Local<Option<PreviewKey>>,
+) {
+    let key = carried_q.iter().next().map(|(e, t, piece)| {
+        let mut hexes: Vec<_> = piece
+            .hexes()
+            .map(|(hex, data)| (*hex, data.connections))
+            .collect();
+        hexes.sort_by_key(|(hex, _)| (hex.x, hex.y));
+
+        PreviewKey {
+            piece: e,
+            hex: piece
+                .target_hex()
+                .unwrap_or_else(|| map_layout.world_pos_to_hex(t.translation.truncate())),
+            hexes,
+        }
+    });
+
+    // only rebuild when the piece moves to another hex or rotates
+    if *last_key == key && !settings.is_changed() {
+        return;
+    }
+
+    for e in preview_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+
+    if let Some(key) = &key {
+        let (_, _, piece) = carried_q.get(key.piece).unwrap();
+
+        for (hex, _) in key.hexes.iter() {
+            let hex = key.hex + *hex;
+
+            // hexes off the board are left out
+            let Some(map_hex) = map.hexes.get(&hex) else {
+                continue;
+            };
+
+            let mut sprite = TextureAtlasSprite::new(EMPTY_TILE_INDEX);
+            sprite.color = if map_hex.placed_hex_e.is_none() {
+                settings.palette().free_hex
+            } else {
+                settings.palette().taken_hex
+            };
+
+            cmd.spawn((
+                SpriteSheetBundle {
+                    transform: Transform::from_translation(
+                        map_layout.hex_to_world_pos(hex).extend(HEX_Z),
+                    ),
+                    sprite,
+                    texture_atlas: sprites.tiles.clone(),
+                    ..default()
+                },
+                PlacementPreview,
+                ResettableGrid,
+            ));
+        }
+
+        if piece.fits(&map, key.hex) {
+            for (edge_conn, preview) in piece.preview_edges(&map, key.hex) {
+                let pos = (map_layout.hex_to_world_pos(edge_conn.first())
+                    + map_layout.hex_to_world_pos(edge_conn.second()))
+                    / 2.;
+
+                cmd.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: edge_color(preview, settings.palette()),
+                            custom_size: Some(Vec2::splat(14.)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(pos.extend(EDGE_Z))
+                            .with_rotation(Quat::from_rotation_z(45f32.to_radians())),
+                        ..default()
+                    },
+                    PlacementPreview,
+                    ResettableGrid,
+                ));
+            }
+        }
+    }
+
+    *last_key = key;
+}
+
+fn clear_preview(mut cmd: Commands, preview_q: Query<Entity, With<PlacementPreview>>) {
+    for e in preview_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+}
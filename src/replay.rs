@@ -0,0 +1,405 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    game_mode::GameMode,
+    history::UndoRequest,
+    level::TestLevel,
+    map::BoardSeed,
+    menu::{ButtonColors, RunSystem},
+    pause::AbandonedRun,
+    piece::{LotSlot, Piece, PieceId, PlacePieceRequest, RotatePieceRequest},
+    reset::{RegisteredSystems, Resettable},
+    score::{Level, Score},
+    stash::StashPieceRequest,
+    storage::{KeyValueStore, Storage},
+    GameState,
+};
+use bevy::{ecs::system::SystemId, prelude::*};
+use bevy_tweening::{Animator, EaseFunction};
+use hexx::Hex;
+use serde::{Deserialize, Serialize};
+
+const LAST_REPLAY_KEY: &str = "last_replay.ron";
+/// Slack for rounding errors of the recorded timestamps, well below a frame.
+const PLAYBACK_SLACK_SECS: f32 = 0.0005;
+
+/// Everything the player did in a run, enough to play it again from its seed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    /// Seconds the run lasted.
+    pub secs: f32,
+    /// Outcome of the run, a playback ending with a different one has diverged.
+    pub score: u32,
+    pub level: u32,
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    /// Seconds since the start of the run.
+    pub secs: f32,
+    pub action: ReplayAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayAction {
+    /// Lot offered to the player, the pieces ordered by their lot slot.
+    /// It follows from the seed, so it's only recorded to spot a diverged playback.
+    Offered(Vec<OfferedPiece>),
+    Rotate {
+        piece: PieceId,
+        pivot: Hex,
+        clockwise: bool,
+    },
+    Place {
+        piece: PieceId,
+        hex: Hex,
+    },
+    Stash(PieceId),
+    Undo,
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferedPiece {
+    pub id: PieceId,
+    /// Hexes relative to the piece origin with their connected sides as a bitmask.
+    pub hexes: Vec<(Hex, Option<u8>)>,
+}
+
+impl Replay {
+    pub fn load(store: &dyn KeyValueStore) -> Option<Self> {
+        store
+            .load(LAST_REPLAY_KEY)
+            .and_then(|data| match ron::from_str(&data) {
+                Ok(replay) => Some(replay),
+                Err(e) => {
+                    warn!("Failed to parse the last replay: {e}");
+                    None
+                }
+            })
+    }
+
+    pub fn save(&self, store: &mut dyn KeyValueStore) {
+        match ron::to_string(self) {
+            Ok(data) => store.save(LAST_REPLAY_KEY, &data),
+            Err(e) => warn!("Failed to serialize the last replay: {e}"),
+        }
+    }
+
+    /// Lots offered in the run, in order.
+    pub fn offered(&self) -> impl Iterator<Item = &Vec<OfferedPiece>> {
+        self.events.iter().filter_map(|ev| match &ev.action {
+            ReplayAction::Offered(lot) => Some(lot),
+            _ => None,
+        })
+    }
+
+    /// Whether a playback of this replay ended up as `played`.
+    pub fn matches(&self, played: &Replay) -> bool {
+        self.score == played.score
+            && self.level == played.level
+            && self.offered().eq(played.offered())
+    }
+}
+
+/// Replay of the last finished run.
+#[derive(Debug, Resource, Default)]
+pub struct LastReplay(pub Option<Replay>);
+
+/// Actions of the current run recorded so far.
+#[derive(Debug, Resource, Default)]
+pub struct ReplayRecorder {
+    /// Elapsed app time the run started at.
+    started_at: f64,
+    events: Vec<ReplayEvent>,
+}
+
+/// Replay that's being played back.
+/// Its actions are sent as the same requests the player input sends, at the time they were recorded.
+/// Player input isn't blocked, so interacting with the board makes the playback diverge.
+#[derive(Debug, Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    /// Index of the next event to play.
+    next: usize,
+}
+
+impl ReplayPlayback {
+    /// The next run gets played from the start of `replay`.
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+}
+
+/// Sent when the board gets skipped.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct BoardSkipped;
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LastReplay>()
+            .init_resource::<ReplayRecorder>()
+            .add_event::<BoardSkipped>()
+            .add_systems(Startup, load_last_replay)
+            .add_systems(OnEnter(GameState::Game), start_recording)
+            .add_systems(OnExit(GameState::Game), finish_recording)
+            .add_systems(OnEnter(GameState::GameOver), stop_playback)
+            .add_systems(OnEnter(GameState::Tutorial), stop_playback)
+            .add_systems(
+                PreUpdate,
+                play_actions.run_if(
+                    in_state(GameState::Game).and_then(resource_exists::<ReplayPlayback>()),
+                ),
+            )
+            // requests are recorded at the end of the frame they were sent in
+            .add_systems(Last, record_actions.run_if(in_state(GameState::Game)));
+    }
+}
+
+fn load_last_replay(mut last_replay: ResMut<LastReplay>, storage: Res<Storage>) {
+    last_replay.0 = Replay::load(storage.0.as_ref());
+}
+
+fn start_recording(mut recorder: ResMut<ReplayRecorder>, time: Res<Time>) {
+    recorder.started_at = time.elapsed_seconds_f64();
+    recorder.events.clear();
+}
+
+fn record_actions(
+    mut recorder: ResMut<ReplayRecorder>,
+    time: Res<Time>,
+    mut place_ev_r: EventReader<PlacePieceRequest>,
+    mut rotate_ev_r: EventReader<RotatePieceRequest>,
+    mut stash_ev_r: EventReader<StashPieceRequest>,
+    mut undo_ev_r: EventReader<UndoRequest>,
+    mut skip_ev_r: EventReader<BoardSkipped>,
+    offered_q: Query<(&PieceId, &LotSlot, &Piece), Added<PieceId>>,
+    id_q: Query<&PieceId>,
+) {
+    let secs = (time.elapsed_seconds_f64() - recorder.started_at) as f32;
+    let mut actions = Vec::new();
+
+    let mut offered: Vec<_> = offered_q.iter().collect();
+    if !offered.is_empty() {
+        offered.sort_by_key(|(_, slot, _)| slot.0);
+        actions.push(ReplayAction::Offered(
+            offered
+                .into_iter()
+                .map(|(id, _, piece)| {
+                    let mut hexes: Vec<_> = piece
+                        .hexes()
+                        .map(|(hex, data)| {
+                            let sides = data.connections.map(|connections| {
+                                (0..6)
+                                    .filter(|side| connections[*side])
+                                    .fold(0, |mask, side| mask | 1 << side)
+                            });
+                            (*hex, sides)
+                        })
+                        .collect();
+                    hexes.sort_by_key(|(hex, _)| (hex.x, hex.y));
+
+                    OfferedPiece { id: *id, hexes }
+                })
+                .collect(),
+        ));
+    }
+
+    actions.extend(skip_ev_r.read().map(|_| ReplayAction::Skip));
+    actions.extend(undo_ev_r.read().map(|_| ReplayAction::Undo));
+    actions.extend(rotate_ev_r.read().filter_map(|ev| {
+        Some(ReplayAction::Rotate {
+            piece: *id_q.get(ev.piece).ok()?,
+            pivot: ev.pivot,
+            clockwise: ev.clockwise,
+        })
+    }));
+    actions.extend(
+        stash_ev_r
+            .read()
+            .filter_map(|ev| Some(ReplayAction::Stash(*id_q.get(ev.piece).ok()?))),
+    );
+    actions.extend(place_ev_r.read().filter_map(|ev| {
+        Some(ReplayAction::Place {
+            piece: *id_q.get(ev.piece).ok()?,
+            hex: ev.hex,
+        })
+    }));
+
+    recorder.events.extend(
+        actions
+            .into_iter()
+            .map(|action| ReplayEvent { secs, action }),
+    );
+}
+
+fn play_actions(
+    mut cmd: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    recorder: Res<ReplayRecorder>,
+    time: Res<Time>,
+    systems: Res<RegisteredSystems>,
+    piece_q: Query<(Entity, &PieceId)>,
+    mut place_ev_w: EventWriter<PlacePieceRequest>,
+    mut rotate_ev_w: EventWriter<RotatePieceRequest>,
+    mut stash_ev_w: EventWriter<StashPieceRequest>,
+    mut undo_ev_w: EventWriter<UndoRequest>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let secs = (time.elapsed_seconds_f64() - recorder.started_at) as f32;
+    let playback = &mut *playback;
+    let piece = |id: PieceId| {
+        piece_q
+            .iter()
+            .find(|(_, piece_id)| **piece_id == id)
+            .map(|(e, _)| e)
+    };
+
+    while let Some(ev) = playback.replay.events.get(playback.next) {
+        if ev.secs > secs + PLAYBACK_SLACK_SECS {
+            break;
+        }
+
+        match ev.action {
+            ReplayAction::Offered(_) => {}
+            ReplayAction::Rotate {
+                piece: id,
+                pivot,
+                clockwise,
+            } => {
+                if let Some(piece) = piece(id) {
+                    rotate_ev_w.send(RotatePieceRequest {
+                        piece,
+                        pivot,
+                        clockwise,
+                    });
+                }
+            }
+            ReplayAction::Place { piece: id, hex } => {
+                if let Some(piece) = piece(id) {
+                    place_ev_w.send(PlacePieceRequest { piece, hex });
+                }
+            }
+            ReplayAction::Stash(id) => {
+                if let Some(piece) = piece(id) {
+                    stash_ev_w.send(StashPieceRequest { piece });
+                }
+            }
+            ReplayAction::Undo => undo_ev_w.send(UndoRequest),
+            ReplayAction::Skip => cmd.run_system(systems.skip_board),
+        }
+
+        playback.next += 1;
+    }
+
+    if secs + PLAYBACK_SLACK_SECS >= playback.replay.secs {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn finish_recording(
+    recorder: Res<ReplayRecorder>,
+    seed: Res<BoardSeed>,
+    mode: Res<GameMode>,
+    score: Res<Score>,
+    level: Res<Level>,
+    time: Res<Time>,
+    test_level: Option<Res<TestLevel>>,
+    playback: Option<Res<ReplayPlayback>>,
+    abandoned: Option<Res<AbandonedRun>>,
+    mut last_replay: ResMut<LastReplay>,
+    mut storage: ResMut<Storage>,
+) {
+    // test plays of edited levels can't be played back from the seed,
+    // abandoned runs don't replace the last one
+    if test_level.is_some() || abandoned.is_some() {
+        return;
+    }
+
+    let replay = Replay {
+        seed: seed.seed,
+        mode: *mode,
+        secs: (time.elapsed_seconds_f64() - recorder.started_at) as f32,
+        score: score.0,
+        level: level.0,
+        events: recorder.events.clone(),
+    };
+
+    match playback {
+        Some(playback) => {
+            if !playback.replay.matches(&replay) {
+                warn!("Replay playback diverged from the recorded run");
+            }
+        }
+        None => replay.save(storage.0.as_mut()),
+    }
+
+    last_replay.0 = Some(replay);
+}
+
+fn stop_playback(mut cmd: Commands) {
+    cmd.remove_resource::<ReplayPlayback>();
+}
+
+/// Plays back the last finished run.
+pub fn play_last_replay(
+    mut cmd: Commands,
+    last_replay: Res<LastReplay>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(replay) = last_replay.0.clone() else {
+        return;
+    };
+
+    *mode = replay.mode;
+    cmd.insert_resource(ReplayPlayback::new(replay));
+    next_state.set(GameState::Game);
+}
+
+pub fn spawn_replay_btn(
+    children: &mut ChildBuilder,
+    tween_delay_ms: u64,
+    font: Handle<Font>,
+    play_replay: SystemId,
+) -> Entity {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::top(Val::Px(20.)),
+                    ..Default::default()
+                },
+                background_color: button_colors.normal.into(),
+                transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                ..Default::default()
+            },
+            button_colors,
+            RunSystem(play_replay),
+            Animator::new(delay_tween(
+                get_scale_tween(None, Vec3::ONE, 350, EaseFunction::BackOut),
+                tween_delay_ms,
+            )),
+            Resettable,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "REPLAY",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::rgb_u8(61, 51, 51),
+                    font,
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
@@ -5,10 +5,15 @@ use leafwing_input_manager::prelude::*;
 
 use crate::{
     animation::{get_relative_scale_anim, get_scale_tween, DespawnOnTweenCompleted},
+    daily::copy_daily_result,
+    game_mode::{cycle_mode, end_run},
     input::GameAction,
     loading::MainCam,
-    map::spawn_grid,
-    score::UpdateTimerEv,
+    map::{spawn_grid, BoardSeed},
+    pause::{open_pause_settings, pause_game, quit_run, restart_run, resume_game},
+    replay::{play_last_replay, BoardSkipped},
+    score::{Streak, UpdateTimerEv},
+    settings::Settings,
     GameState,
 };
 
@@ -20,6 +25,15 @@ pub struct RegisteredSystems {
     pub reset: SystemId,
     pub spawn_board: SystemId,
     pub skip_board: SystemId,
+    pub end_run: SystemId,
+    pub cycle_mode: SystemId,
+    pub play_replay: SystemId,
+    pub copy_daily_result: SystemId,
+    pub pause: SystemId,
+    pub resume: SystemId,
+    pub restart: SystemId,
+    pub quit: SystemId,
+    pub pause_settings: SystemId,
 }
 
 #[derive(Component)]
@@ -32,6 +46,15 @@ impl Plugin for ResetPlugin {
             reset: app.world.register_system(reset_board),
             spawn_board: app.world.register_system(spawn_grid),
             skip_board: app.world.register_system(skip_board),
+            end_run: app.world.register_system(end_run),
+            cycle_mode: app.world.register_system(cycle_mode),
+            play_replay: app.world.register_system(play_last_replay),
+            copy_daily_result: app.world.register_system(copy_daily_result),
+            pause: app.world.register_system(pause_game),
+            resume: app.world.register_system(resume_game),
+            restart: app.world.register_system(restart_run),
+            quit: app.world.register_system(quit_run),
+            pause_settings: app.world.register_system(open_pause_settings),
         };
 
         app.insert_resource(systems);
@@ -40,12 +63,19 @@ impl Plugin for ResetPlugin {
 
 fn skip_board(
     mut cmd: Commands,
+    settings: Res<Settings>,
     systems: Res<RegisteredSystems>,
     mut ev_w: EventWriter<UpdateTimerEv>,
+    mut skip_ev_w: EventWriter<BoardSkipped>,
+    mut seed: ResMut<BoardSeed>,
+    mut streak: ResMut<Streak>,
 ) {
+    seed.reroll += 1;
+    streak.reset();
     cmd.run_system(systems.reset);
-    cmd.add_trauma(0.7);
+    cmd.add_trauma(settings.trauma(0.7));
     ev_w.send(UpdateTimerEv(-5.));
+    skip_ev_w.send(BoardSkipped);
 }
 
 fn reset_board(
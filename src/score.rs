@@ -1,11 +1,16 @@
 use crate::{
     animation::{delay_tween, get_scale_anim, get_scale_tween, DespawnOnTweenCompleted},
+    connectivity::HouseConnectivity,
+    game_mode::{GameMode, RunClock, MARATHON_HIVES},
+    input::{GameAction, KeyBindings},
     loading::FontAssets,
-    map::{EdgeConnection, WorldMap},
+    map::{BoardSeed, EdgeConnection, WorldMap},
     map_completion::CompletedMap,
     menu::{ButtonColors, RunSystem},
     piece::Piece,
     reset::{RegisteredSystems, Resettable},
+    settings::Settings,
+    stash::{StashSettings, Stashed},
     GameState,
 };
 use bevy::{ecs::system::SystemId, prelude::*};
@@ -22,11 +27,18 @@ impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Score>()
             .init_resource::<Level>()
+            .init_resource::<Streak>()
             .add_event::<UpdateScoreEv>()
             .add_event::<UpdateTimerEv>()
             .add_systems(
                 OnEnter(GameState::Game),
-                (setup_ui, restart_timer, restart_level, restart_score),
+                (
+                    setup_ui,
+                    restart_timer,
+                    restart_level,
+                    restart_score,
+                    restart_streak,
+                ),
             )
             .add_systems(OnEnter(GameState::GameOver), (restart_level,))
             .add_systems(
@@ -34,9 +46,13 @@ impl Plugin for ScorePlugin {
                 (
                     update_score,
                     update_score_text,
+                    update_clock_text.run_if(not(resource_exists::<GameTimer>())),
+                    update_streak_text,
                     update_pieces_text,
+                    update_houses_text,
+                    update_hold_text.run_if(resource_changed::<Settings>()),
                     (update_timer, tick_timer).run_if(resource_exists::<GameTimer>()),
-                    update_level.run_if(resource_added::<CompletedMap>()),
+                    (update_level, update_streak).run_if(resource_added::<CompletedMap>()),
                 )
                     .distributive_run_if(in_state(GameState::Game)),
             );
@@ -52,6 +68,20 @@ struct TimerText;
 #[derive(Component)]
 struct PiecesText;
 
+#[derive(Component)]
+struct HousesText;
+
+/// Label naming the stash key.
+#[derive(Component)]
+pub struct HoldText;
+
+#[derive(Component)]
+struct StreakText;
+
+/// Hives completed under this many seconds raise the streak twice.
+const FAST_HIVE_SECS: f32 = 45.;
+const MAX_MULTIPLIER: f32 = 3.;
+
 #[derive(Debug, Resource, Default, Deref, DerefMut)]
 pub struct Score(pub u32);
 
@@ -61,13 +91,43 @@ pub struct Level(pub u32);
 #[derive(Debug, Resource, Default, Event)]
 pub struct UpdateScoreEv(pub i32);
 
+/// Consecutive clean hives.
+/// Completing a hive without dead ends raises it, dead ends or skipping break it.
+#[derive(Debug, Resource, Default)]
+pub struct Streak {
+    pub count: u32,
+}
+
+impl Streak {
+    /// Multiplier of the points gained.
+    /// Dead end penalties aren't multiplied, so a streak only ever adds points.
+    pub fn multiplier(&self) -> f32 {
+        (1. + self.count as f32 * 0.25).min(MAX_MULTIPLIER)
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
 #[derive(Debug, Resource, Deref, DerefMut)]
 pub struct GameTimer(pub Timer);
 
 #[derive(Debug, Resource, Default, Event)]
 pub struct UpdateTimerEv(pub f32);
 
-fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSystems>) {
+fn hold_label(bindings: &KeyBindings) -> String {
+    format!("HOLD [{}]", bindings.key_label(GameAction::Stash))
+}
+
+fn setup_ui(
+    mut cmd: Commands,
+    fonts: Res<FontAssets>,
+    systems: Res<RegisteredSystems>,
+    mode: Res<GameMode>,
+    stash_settings: Res<StashSettings>,
+    settings: Res<Settings>,
+) {
     cmd.spawn(NodeBundle {
         style: Style {
             width: Val::Percent(100.0),
@@ -89,7 +149,11 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSy
         .with_children(|b| {
             b.spawn((
                 TextBundle::from_section(
-                    "SCORE",
+                    if *mode == GameMode::Marathon {
+                        "HIVES"
+                    } else {
+                        "SCORE"
+                    },
                     TextStyle {
                         font_size: 40.0,
                         color: Color::rgb_u8(61, 51, 51),
@@ -104,24 +168,52 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSy
                 Resettable,
             ));
 
-            b.spawn((
-                TextBundle::from_section(
-                    "0",
-                    TextStyle {
-                        font_size: 60.0,
-                        color: Color::rgb_u8(61, 51, 51),
-                        font: fonts.main.clone(),
-                        ..default()
-                    },
-                )
-                .with_style(Style {
-                    // width: Val::Px(60.),
-                    margin: UiRect::horizontal(Val::Px(40.)),
+            b.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
                     ..default()
-                }),
-                ScoreText,
-                Resettable,
-            ));
+                },
+                ..default()
+            })
+            .with_children(|b| {
+                b.spawn((
+                    TextBundle::from_section(
+                        "0",
+                        TextStyle {
+                            font_size: 60.0,
+                            color: Color::rgb_u8(61, 51, 51),
+                            font: fonts.main.clone(),
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        // width: Val::Px(60.),
+                        margin: UiRect::horizontal(Val::Px(40.)),
+                        ..default()
+                    }),
+                    ScoreText,
+                    Resettable,
+                ));
+
+                b.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::rgb_u8(61, 51, 51),
+                            font: fonts.main.clone(),
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(-20.),
+                        ..default()
+                    }),
+                    StreakText,
+                    Resettable,
+                ));
+            });
 
             let button_colors = ButtonColors::default();
             b.spawn((
@@ -157,6 +249,42 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSy
                     },
                 ));
             });
+
+            b.spawn((
+                TextBundle::from_section(
+                    hold_label(&settings.bindings),
+                    TextStyle {
+                        font_size: 25.0,
+                        color: Color::rgb_u8(61, 51, 51),
+                        font: fonts.main.clone(),
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::top(Val::Px(30.)),
+                    ..default()
+                }),
+                HoldText,
+                Resettable,
+            ));
+
+            b.spawn((
+                TextBundle::from_section(
+                    // only the timer pays for swaps
+                    if mode.is_timed() && stash_settings.swap_time_cost > 0. {
+                        format!("SWAP -{}s", stash_settings.swap_time_cost)
+                    } else {
+                        "SWAP FREE".to_string()
+                    },
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::rgb_u8(61, 51, 51),
+                        font: fonts.main.clone(),
+                        ..default()
+                    },
+                ),
+                Resettable,
+            ));
         });
 
         b.spawn(NodeBundle {
@@ -202,6 +330,33 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSy
                 Resettable,
                 PiecesText,
             ));
+
+            b.spawn((
+                TextBundle::from_section(
+                    "HOUSES",
+                    TextStyle {
+                        font_size: 25.0,
+                        color: Color::rgb_u8(61, 51, 51),
+                        font: fonts.main.clone(),
+                        ..default()
+                    },
+                ),
+                Resettable,
+            ));
+
+            b.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::rgb_u8(61, 51, 51),
+                        font: fonts.main.clone(),
+                        ..default()
+                    },
+                ),
+                Resettable,
+                HousesText,
+            ));
         });
 
         b.spawn(NodeBundle {
@@ -252,10 +407,29 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSy
     });
 }
 
-fn update_score_text(score: Res<Score>, mut text_q: Query<&mut Text, With<ScoreText>>) {
-    if score.is_changed() {
+fn update_score_text(
+    score: Res<Score>,
+    level: Res<Level>,
+    mode: Res<GameMode>,
+    mut text_q: Query<&mut Text, With<ScoreText>>,
+) {
+    if score.is_changed() || level.is_changed() {
         if let Ok(mut text) = text_q.get_single_mut() {
-            text.sections[0].value = format!("{}", score.0);
+            text.sections[0].value = if *mode == GameMode::Marathon {
+                format!("{} / {}", level.0, MARATHON_HIVES)
+            } else {
+                format!("{}", score.0)
+            };
+        }
+    }
+}
+
+fn update_clock_text(clock: Res<RunClock>, mut text_q: Query<&mut Text, With<TimerText>>) {
+    if let Ok(mut text) = text_q.get_single_mut() {
+        let txt = format!("{:.0}", clock.elapsed_secs().floor());
+
+        if text.sections[0].value != txt {
+            text.sections[0].value = txt;
         }
     }
 }
@@ -264,8 +438,16 @@ fn update_score(
     mut cmd: Commands,
     mut ev_r: EventReader<UpdateScoreEv>,
     mut score: ResMut<Score>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
     text_q: Query<Entity, With<ScoreText>>,
 ) {
+    // marathons are scored by time once they're over
+    if *mode == GameMode::Marathon {
+        ev_r.clear();
+        return;
+    }
+
     for ev in ev_r.read() {
         score.0 = score.0.saturating_add_signed(ev.0);
 
@@ -286,7 +468,7 @@ fn update_score(
             ));
 
             if ev.0 < 0 {
-                cmd.add_trauma(0.3);
+                cmd.add_trauma(settings.trauma(0.3));
             }
         }
     }
@@ -294,11 +476,15 @@ fn update_score(
 
 fn update_pieces_text(
     mut cmd: Commands,
-    pieces_q: Query<(), With<Piece>>,
+    pieces_q: Query<(), (With<Piece>, Without<Stashed>)>,
     mut text_q: Query<(Entity, &mut Text), With<PiecesText>>,
 ) {
     if let Ok((e, mut text)) = text_q.get_single_mut() {
-        let count = if pieces_q.iter().len() == 2 { 1 } else { 2 };
+        // the last piece of a lot is discarded, unless stashing left it as the only one
+        let count = match pieces_q.iter().len() {
+            1 | 2 => 1,
+            _ => 2,
+        };
         let txt = format!("{}", count);
         if text.sections[0].value != txt {
             text.sections[0].value = txt;
@@ -320,8 +506,107 @@ fn update_pieces_text(
     }
 }
 
-fn restart_timer(mut cmd: Commands) {
-    cmd.insert_resource(GameTimer(Timer::from_seconds(150., TimerMode::Once)));
+fn update_houses_text(
+    mut cmd: Commands,
+    connectivity: Res<HouseConnectivity>,
+    mut text_q: Query<(Entity, &mut Text), With<HousesText>>,
+) {
+    if let Ok((e, mut text)) = text_q.get_single_mut() {
+        let txt = format!(
+            "{} / {}",
+            connectivity.connected_count(),
+            connectivity.house_count()
+        );
+        if text.sections[0].value != txt {
+            // only pop when the count changes, not when the text is filled in
+            if !text.sections[0].value.is_empty() {
+                cmd.entity(e).try_insert(Animator::new(
+                    get_scale_tween(
+                        None,
+                        (Vec2::ONE * 1.5).extend(1.),
+                        250,
+                        EaseFunction::BackOut,
+                    )
+                    .then(get_scale_tween(
+                        None,
+                        Vec3::ONE,
+                        200,
+                        EaseFunction::QuadraticOut,
+                    )),
+                ));
+            }
+
+            text.sections[0].value = txt;
+        }
+    }
+}
+
+pub fn update_streak(
+    mut streak: ResMut<Streak>,
+    map: Res<WorldMap>,
+    completed_map: Res<CompletedMap>,
+    time: Res<Time>,
+) {
+    if !completed_map.dead_ends.is_empty() {
+        streak.reset();
+        return;
+    }
+
+    streak.count += 1;
+
+    if time.elapsed_seconds() - map.started_at < FAST_HIVE_SECS {
+        streak.count += 1;
+    }
+}
+
+fn update_hold_text(settings: Res<Settings>, mut text_q: Query<&mut Text, With<HoldText>>) {
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = hold_label(&settings.bindings);
+    }
+}
+
+fn update_streak_text(
+    mut cmd: Commands,
+    streak: Res<Streak>,
+    mut text_q: Query<(Entity, &mut Text), With<StreakText>>,
+) {
+    if let Ok((e, mut text)) = text_q.get_single_mut() {
+        let txt = if streak.count > 0 {
+            format!("x{}", streak.multiplier())
+        } else {
+            String::new()
+        };
+
+        if text.sections[0].value != txt {
+            text.sections[0].value = txt;
+            cmd.entity(e).try_insert(Animator::new(
+                get_scale_tween(
+                    None,
+                    (Vec2::ONE * 1.5).extend(1.),
+                    250,
+                    EaseFunction::BackOut,
+                )
+                .then(get_scale_tween(
+                    None,
+                    Vec3::ONE,
+                    200,
+                    EaseFunction::QuadraticOut,
+                )),
+            ));
+        }
+    }
+}
+
+fn restart_streak(mut streak: ResMut<Streak>) {
+    streak.reset();
+}
+
+fn restart_timer(mut cmd: Commands, mode: Res<GameMode>) {
+    if mode.is_timed() {
+        cmd.insert_resource(GameTimer(Timer::from_seconds(150., TimerMode::Once)));
+    } else {
+        cmd.remove_resource::<GameTimer>();
+    }
 }
 
 fn restart_level(mut cmd: Commands) {
@@ -336,6 +621,7 @@ fn tick_timer(
     mut cmd: Commands,
     mut timer: ResMut<GameTimer>,
     time: Res<Time>,
+    settings: Res<Settings>,
     mut text_q: Query<&mut Text, With<TimerText>>,
     mut next: ResMut<NextState<GameState>>,
 ) {
@@ -350,7 +636,7 @@ fn tick_timer(
     }
 
     if timer.just_finished() {
-        cmd.add_trauma(0.3);
+        cmd.add_trauma(settings.trauma(0.3));
         next.set(GameState::GameOver);
     }
 }
@@ -384,6 +670,7 @@ fn update_timer(
     }
 }
 
-fn update_level(mut lvl: ResMut<Level>) {
+pub fn update_level(mut lvl: ResMut<Level>, mut seed: ResMut<BoardSeed>) {
     lvl.0 += 1;
+    seed.reroll = 0;
 }
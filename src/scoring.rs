@@ -0,0 +1,210 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    loading::FontAssets,
+    map::WorldMap,
+    map_completion::CompletedMap,
+    reset::ResettableGrid,
+    GameState,
+};
+use bevy::{prelude::*, utils::HashSet};
+use bevy_tweening::{Animator, EaseFunction};
+use hexx::Hex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreItem {
+    pub label: &'static str,
+    pub points: i32,
+}
+
+/// Itemised score of a completed hive.
+#[derive(Debug, Clone, Resource)]
+pub struct ScoreBreakdown {
+    pub items: Vec<ScoreItem>,
+    /// Streak multiplier the hive was started with.
+    pub multiplier: f32,
+}
+
+impl Default for ScoreBreakdown {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            multiplier: 1.,
+        }
+    }
+}
+
+impl ScoreBreakdown {
+    fn push(&mut self, label: &'static str, points: i32) {
+        if points != 0 {
+            self.items.push(ScoreItem { label, points });
+        }
+    }
+
+    /// Sum of the items, without the streak multiplier.
+    pub fn total(&self) -> i32 {
+        self.items.iter().map(|item| item.points).sum()
+    }
+
+    /// Points of everything but the dead ends, multiplied by the streak if there's a gain.
+    pub fn route_points(&self) -> i32 {
+        let points = self.total() - self.points(DEAD_ENDS);
+
+        if points > 0 {
+            (points as f32 * self.multiplier).round() as i32
+        } else {
+            points
+        }
+    }
+
+    /// Points the hive adds to the score, the dead ends aren't multiplied.
+    pub fn streak_total(&self) -> i32 {
+        self.route_points() + self.points(DEAD_ENDS)
+    }
+
+    pub fn points(&self, label: &str) -> i32 {
+        self.items
+            .iter()
+            .filter(|item| item.label == label)
+            .map(|item| item.points)
+            .sum()
+    }
+}
+
+pub const HOUSES: &str = "HOUSES";
+pub const DEAD_ENDS: &str = "DEAD ENDS";
+pub const SHORT_ROUTES: &str = "SHORT ROUTES";
+pub const FEW_PIECES: &str = "FEW PIECES";
+pub const UNUSED_HEXES: &str = "UNUSED HEXES";
+pub const LOOPS: &str = "LOOPS";
+pub const EMPTY_HEXES: &str = "EMPTY HEXES";
+pub const SPEED: &str = "SPEED";
+
+/// Scores a completed hive, `secs` being the time it took to build.
+pub fn evaluate(map: &WorldMap, completed_map: &CompletedMap, secs: f32) -> ScoreBreakdown {
+    let mut breakdown = ScoreBreakdown::default();
+    let house_count = map.house_count() as i32;
+    let houses: HashSet<_> = map.houses().collect();
+
+    breakdown.push(HOUSES, house_count * 10);
+    breakdown.push(DEAD_ENDS, -(completed_map.dead_ends.len() as i32));
+
+    // routes include the houses at both of their ends
+    let route_hexes: HashSet<Hex> = completed_map
+        .routes
+        .iter()
+        .flatten()
+        .filter(|hex| !houses.contains(hex))
+        .copied()
+        .collect();
+    let detours: u32 = completed_map
+        .routes
+        .iter()
+        .filter_map(|route| Some((route.first()?, route.last()?, route.len() as u32)))
+        .map(|(start, end, len)| {
+            (len.saturating_sub(2)).saturating_sub(start.unsigned_distance_to(*end) - 1)
+        })
+        .sum();
+    breakdown.push(SHORT_ROUTES, 5i32.saturating_sub(detours as i32).max(0));
+
+    breakdown.push(
+        FEW_PIECES,
+        (house_count * 2 - map.placed_pieces() as i32).max(0),
+    );
+    breakdown.push(
+        UNUSED_HEXES,
+        -(map
+            .route_hexes()
+            .filter(|hex| !route_hexes.contains(hex))
+            .count() as i32),
+    );
+    breakdown.push(LOOPS, map.loop_count() as i32 * 2);
+    breakdown.push(EMPTY_HEXES, map.empty_hexes().count() as i32 / 4);
+    breakdown.push(SPEED, (10. - secs / 6.).max(0.) as i32);
+
+    breakdown
+}
+
+pub struct ScoringPlugin;
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            show_breakdown.run_if(
+                in_state(GameState::Game).and_then(resource_exists_and_changed::<ScoreBreakdown>()),
+            ),
+        );
+    }
+}
+
+fn show_breakdown(mut cmd: Commands, breakdown: Res<ScoreBreakdown>, fonts: Res<FontAssets>) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::rgb_u8(61, 51, 51),
+        font: fonts.main.clone(),
+    };
+
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                bottom: Val::Px(30.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        ResettableGrid,
+    ))
+    .with_children(|b| {
+        for (i, item) in breakdown.items.iter().enumerate() {
+            b.spawn((
+                TextBundle {
+                    transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                    ..TextBundle::from_section(
+                        format!("{} {:+}", item.label, item.points),
+                        text_style(25.),
+                    )
+                },
+                Animator::new(delay_tween(
+                    get_scale_tween(None, Vec3::ONE, 300, EaseFunction::BackOut),
+                    300 + i as u64 * 120,
+                )),
+            ));
+        }
+
+        let mut lines = breakdown.items.len() as u64;
+
+        if breakdown.multiplier > 1. {
+            b.spawn((
+                TextBundle {
+                    transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                    ..TextBundle::from_section(
+                        format!("x{} STREAK", breakdown.multiplier),
+                        text_style(25.),
+                    )
+                },
+                Animator::new(delay_tween(
+                    get_scale_tween(None, Vec3::ONE, 300, EaseFunction::BackOut),
+                    300 + lines * 120,
+                )),
+            ));
+            lines += 1;
+        }
+
+        b.spawn((
+            TextBundle {
+                transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                ..TextBundle::from_section(
+                    format!("TOTAL {:+}", breakdown.streak_total()),
+                    text_style(40.),
+                )
+            },
+            Animator::new(delay_tween(
+                get_scale_tween(None, Vec3::ONE, 300, EaseFunction::BackOut),
+                300 + lines * 120,
+            )),
+        ));
+    });
+}
@@ -0,0 +1,602 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween},
+    input::{GameAction, KeyBindings},
+    loading::FontAssets,
+    menu::{ButtonColors, ChangeState, RunSystem},
+    pause::PauseState,
+    reset::{tween_reset, RegisteredSystems, Resettable},
+    storage::{KeyValueStore, Storage},
+    GameState,
+};
+use bevy::{audio::GlobalVolume, prelude::*, ui::FocusPolicy};
+use bevy_tweening::{Animator, EaseFunction};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_KEY: &str = "settings.ron";
+
+/// Player preferences, saved whenever they change and applied at startup.
+#[derive(Debug, Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub bindings: KeyBindings,
+    /// Multiplier of the screen shake trauma.
+    pub shake: f32,
+    /// Playback speed of the tweens.
+    pub animation_speed: f32,
+    pub colorblind: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            music_volume: 0.7,
+            sfx_volume: 1.,
+            bindings: KeyBindings::default(),
+            shake: 1.,
+            animation_speed: 1.,
+            colorblind: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(store: &dyn KeyValueStore) -> Self {
+        store
+            .load(SETTINGS_KEY)
+            .and_then(|data| match ron::from_str(&data) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    warn!("Failed to parse settings: {e}");
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, store: &mut dyn KeyValueStore) {
+        match ron::to_string(self) {
+            Ok(data) => store.save(SETTINGS_KEY, &data),
+            Err(e) => warn!("Failed to serialize settings: {e}"),
+        }
+    }
+
+    /// Screen shake trauma scaled by the shake intensity.
+    pub fn trauma(&self, trauma: f32) -> f32 {
+        trauma * self.shake
+    }
+
+    pub fn palette(&self) -> &'static Palette {
+        if self.colorblind {
+            &COLORBLIND_PALETTE
+        } else {
+            &DEFAULT_PALETTE
+        }
+    }
+}
+
+/// Colours telling the state of the board apart.
+#[derive(Debug)]
+pub struct Palette {
+    pub connected: Color,
+    pub open: Color,
+    pub dead_end: Color,
+    pub free_hex: Color,
+    pub taken_hex: Color,
+    /// Tints of the groups of linked houses.
+    pub groups: [Color; 5],
+}
+
+const DEFAULT_PALETTE: Palette = Palette {
+    connected: Color::rgb(0.47, 0.78, 0.35),
+    open: Color::rgb(0.98, 0.86, 0.51),
+    dead_end: Color::rgb(0.86, 0.31, 0.27),
+    free_hex: Color::rgba(0.47, 0.78, 0.35, 0.43),
+    taken_hex: Color::rgba(0.86, 0.31, 0.27, 0.43),
+    groups: [
+        Color::rgb(1., 0.78, 0.35),
+        Color::rgb(0.55, 0.78, 1.),
+        Color::rgb(0.67, 0.9, 0.51),
+        Color::rgb(0.94, 0.59, 0.78),
+        Color::rgb(0.78, 0.67, 1.),
+    ],
+};
+
+/// Okabe-Ito colours, which stay distinct with the common kinds of colour blindness.
+const COLORBLIND_PALETTE: Palette = Palette {
+    connected: Color::rgb(0., 0.45, 0.7),
+    open: Color::rgb(0.94, 0.89, 0.26),
+    dead_end: Color::rgb(0.84, 0.37, 0.),
+    free_hex: Color::rgba(0., 0.45, 0.7, 0.43),
+    taken_hex: Color::rgba(0.84, 0.37, 0., 0.43),
+    groups: [
+        Color::rgb(0.9, 0.62, 0.),
+        Color::rgb(0.34, 0.71, 0.91),
+        Color::rgb(0., 0.62, 0.45),
+        Color::rgb(0.94, 0.89, 0.26),
+        Color::rgb(0.8, 0.47, 0.65),
+    ],
+};
+
+/// Action waiting for the next released key to be bound to it.
+#[derive(Debug, Resource)]
+pub struct Rebinding(pub GameAction);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slider {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Shake,
+    AnimationSpeed,
+}
+
+impl Slider {
+    fn label(&self) -> &'static str {
+        match self {
+            Slider::MasterVolume => "VOLUME",
+            Slider::MusicVolume => "MUSIC",
+            Slider::SfxVolume => "SFX",
+            Slider::Shake => "SHAKE",
+            Slider::AnimationSpeed => "ANIMATIONS",
+        }
+    }
+
+    fn get(&self, settings: &Settings) -> f32 {
+        match self {
+            Slider::MasterVolume => settings.master_volume,
+            Slider::MusicVolume => settings.music_volume,
+            Slider::SfxVolume => settings.sfx_volume,
+            Slider::Shake => settings.shake,
+            Slider::AnimationSpeed => settings.animation_speed,
+        }
+    }
+
+    fn set(&self, settings: &mut Settings, value: f32) {
+        let (min, max) = self.range();
+        // rounded, so repeated steps don't drift
+        let value = (value.clamp(min, max) * 100.).round() / 100.;
+
+        match self {
+            Slider::MasterVolume => settings.master_volume = value,
+            Slider::MusicVolume => settings.music_volume = value,
+            Slider::SfxVolume => settings.sfx_volume = value,
+            Slider::Shake => settings.shake = value,
+            Slider::AnimationSpeed => settings.animation_speed = value,
+        }
+    }
+
+    fn range(&self) -> (f32, f32) {
+        match self {
+            Slider::AnimationSpeed => (0.5, 2.),
+            _ => (0., 1.),
+        }
+    }
+
+    fn step(&self) -> f32 {
+        match self {
+            Slider::AnimationSpeed => 0.25,
+            _ => 0.1,
+        }
+    }
+
+    fn format(&self, value: f32) -> String {
+        match self {
+            Slider::AnimationSpeed => format!("{value:.2}x"),
+            _ => format!("{:.0}%", value * 100.),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum SettingButton {
+    /// Moves the slider by the number of steps.
+    Step(Slider, f32),
+    ToggleColorblind,
+    Rebind(GameAction),
+}
+
+#[derive(Component, Clone, Copy)]
+enum SettingText {
+    Slider(Slider),
+    Colorblind,
+    Binding(GameAction),
+}
+
+impl SettingText {
+    fn value(&self, settings: &Settings, rebinding: Option<&Rebinding>) -> String {
+        match self {
+            SettingText::Slider(slider) => slider.format(slider.get(settings)),
+            SettingText::Colorblind => if settings.colorblind { "ON" } else { "OFF" }.to_string(),
+            SettingText::Binding(action) => {
+                if rebinding.map_or(false, |rebinding| rebinding.0 == *action) {
+                    "...".to_string()
+                } else {
+                    settings.bindings.key_label(*action)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct SettingsPanel;
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .add_systems(Startup, load_settings)
+            .add_systems(OnEnter(GameState::Settings), setup_ui)
+            .add_systems(OnExit(GameState::Settings), (tween_reset, stop_rebinding))
+            .add_systems(OnEnter(PauseState::Settings), setup_pause_ui)
+            .add_systems(
+                OnExit(PauseState::Settings),
+                (despawn_panel, stop_rebinding),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_settings.run_if(resource_changed::<Settings>()),
+                    (
+                        handle_setting_buttons,
+                        rebind_key.run_if(resource_exists::<Rebinding>()),
+                        update_setting_texts,
+                    )
+                        .chain()
+                        .run_if(
+                            in_state(GameState::Settings).or_else(in_state(PauseState::Settings)),
+                        ),
+                    scale_animations::<Transform>,
+                    scale_animations::<Text>,
+                    scale_animations::<Sprite>,
+                    scale_animations::<TextureAtlasSprite>,
+                    scale_animations::<BackgroundColor>,
+                ),
+            );
+    }
+}
+
+fn load_settings(mut settings: ResMut<Settings>, storage: Res<Storage>) {
+    *settings = Settings::load(storage.0.as_ref());
+}
+
+fn apply_settings(mut cmd: Commands, settings: Res<Settings>, mut storage: ResMut<Storage>) {
+    cmd.insert_resource(settings.bindings.input_map());
+    cmd.insert_resource(GlobalVolume::new(settings.master_volume));
+
+    // the first run only applies the loaded settings
+    if !settings.is_added() {
+        settings.save(storage.0.as_mut());
+    }
+}
+
+fn scale_animations<T: Component>(
+    settings: Res<Settings>,
+    mut animator_q: Query<&mut Animator<T>>,
+) {
+    for mut animator in animator_q.iter_mut() {
+        if animator.speed() != settings.animation_speed {
+            animator.set_speed(settings.animation_speed);
+        }
+    }
+}
+
+fn handle_setting_buttons(
+    mut cmd: Commands,
+    mut settings: ResMut<Settings>,
+    button_q: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in button_q.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match *button {
+            SettingButton::Step(slider, steps) => {
+                let value = slider.get(&settings) + slider.step() * steps;
+                slider.set(&mut settings, value);
+            }
+            SettingButton::ToggleColorblind => settings.colorblind = !settings.colorblind,
+            SettingButton::Rebind(action) => cmd.insert_resource(Rebinding(action)),
+        }
+    }
+}
+
+fn rebind_key(
+    mut cmd: Commands,
+    keys: Res<Input<KeyCode>>,
+    rebinding: Res<Rebinding>,
+    mut settings: ResMut<Settings>,
+) {
+    // bound on release, so the action doesn't fire with the key that's still held
+    if let Some(key) = keys.get_just_released().next() {
+        settings.bindings.rebind(rebinding.0, *key);
+        cmd.remove_resource::<Rebinding>();
+    }
+}
+
+fn stop_rebinding(mut cmd: Commands) {
+    cmd.remove_resource::<Rebinding>();
+}
+
+fn update_setting_texts(
+    settings: Res<Settings>,
+    rebinding: Option<Res<Rebinding>>,
+    mut text_q: Query<(&mut Text, &SettingText)>,
+) {
+    for (mut text, setting) in text_q.iter_mut() {
+        let value = setting.value(&settings, rebinding.as_deref());
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+pub fn spawn_settings_btn(
+    children: &mut ChildBuilder,
+    tween_delay_ms: u64,
+    font: Handle<Font>,
+) -> Entity {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(220.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::top(Val::Px(20.)),
+                    ..Default::default()
+                },
+                background_color: button_colors.normal.into(),
+                transform: Transform::from_scale(Vec2::ZERO.extend(1.)),
+                ..Default::default()
+            },
+            button_colors,
+            ChangeState(GameState::Settings),
+            Animator::new(delay_tween(
+                get_scale_tween(None, Vec3::ONE, 350, EaseFunction::BackOut),
+                tween_delay_ms,
+            )),
+            Resettable,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "SETTINGS",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::rgb_u8(61, 51, 51),
+                    font,
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
+
+fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>) {
+    let panel = spawn_panel(&mut cmd, &fonts, ChangeState(GameState::Tutorial));
+    cmd.entity(panel).insert(Resettable);
+}
+
+fn setup_pause_ui(mut cmd: Commands, fonts: Res<FontAssets>, systems: Res<RegisteredSystems>) {
+    spawn_panel(&mut cmd, &fonts, RunSystem(systems.pause));
+}
+
+/// Spawns the settings without tweening them in, so they work while the run is paused.
+fn spawn_panel(cmd: &mut Commands, fonts: &FontAssets, back: impl Bundle) -> Entity {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::rgb_u8(61, 51, 51),
+        font: fonts.main.clone(),
+    };
+
+    cmd.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba_u8(254, 225, 184, 220).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        SettingsPanel,
+    ))
+    .with_children(|b| {
+        b.spawn(
+            TextBundle::from_section("SETTINGS", text_style(70.)).with_style(Style {
+                margin: UiRect::bottom(Val::Px(30.)),
+                ..default()
+            }),
+        );
+
+        b.spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(80.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|b| {
+            b.spawn(column_node()).with_children(|b| {
+                for slider in [
+                    Slider::MasterVolume,
+                    Slider::MusicVolume,
+                    Slider::SfxVolume,
+                    Slider::Shake,
+                    Slider::AnimationSpeed,
+                ] {
+                    b.spawn(row_node()).with_children(|b| {
+                        spawn_label(b, slider.label(), text_style(30.));
+                        spawn_setting_btn(
+                            b,
+                            50.,
+                            TextBundle::from_section("-", text_style(30.)),
+                            SettingButton::Step(slider, -1.),
+                        );
+                        b.spawn((
+                            TextBundle::from_section("", text_style(30.)).with_style(Style {
+                                width: Val::Px(90.),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            }),
+                            SettingText::Slider(slider),
+                        ));
+                        spawn_setting_btn(
+                            b,
+                            50.,
+                            TextBundle::from_section("+", text_style(30.)),
+                            SettingButton::Step(slider, 1.),
+                        );
+                    });
+                }
+
+                b.spawn(row_node()).with_children(|b| {
+                    spawn_label(b, "COLOURBLIND", text_style(30.));
+                    spawn_setting_btn(
+                        b,
+                        120.,
+                        (
+                            TextBundle::from_section("", text_style(30.)),
+                            SettingText::Colorblind,
+                        ),
+                        SettingButton::ToggleColorblind,
+                    );
+                });
+            });
+
+            b.spawn(column_node()).with_children(|b| {
+                for action in [
+                    GameAction::Move,
+                    GameAction::RotateCcw,
+                    GameAction::RotateCw,
+                    GameAction::Undo,
+                    GameAction::Redo,
+                    GameAction::Stash,
+                    GameAction::Pause,
+                ] {
+                    b.spawn(row_node()).with_children(|b| {
+                        spawn_label(b, action_label(action), text_style(30.));
+                        spawn_setting_btn(
+                            b,
+                            150.,
+                            (
+                                TextBundle::from_section("", text_style(30.)),
+                                SettingText::Binding(action),
+                            ),
+                            SettingButton::Rebind(action),
+                        );
+                    });
+                }
+            });
+        });
+
+        b.spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(30.)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|b| {
+            spawn_setting_btn(
+                b,
+                220.,
+                TextBundle::from_section("BACK", text_style(40.)),
+                back,
+            );
+        });
+    })
+    .id()
+}
+
+fn action_label(action: GameAction) -> &'static str {
+    match action {
+        GameAction::Move => "PLACE",
+        GameAction::MoveDir => "MOVE",
+        GameAction::RotateCw => "ROTATE CW",
+        GameAction::RotateCcw => "ROTATE CCW",
+        GameAction::Undo => "UNDO",
+        GameAction::Redo => "REDO",
+        GameAction::Stash => "HOLD",
+        GameAction::Pause => "PAUSE",
+    }
+}
+
+fn column_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(10.),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn row_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            height: Val::Px(45.),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn spawn_label(children: &mut ChildBuilder, label: &str, text_style: TextStyle) {
+    children.spawn(
+        TextBundle::from_section(label, text_style).with_style(Style {
+            width: Val::Px(200.),
+            ..default()
+        }),
+    );
+}
+
+fn spawn_setting_btn(
+    children: &mut ChildBuilder,
+    width: f32,
+    text: impl Bundle,
+    action: impl Bundle,
+) {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: button_colors.normal.into(),
+                ..default()
+            },
+            button_colors,
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(text);
+        });
+}
+
+fn despawn_panel(mut cmd: Commands, panel_q: Query<Entity, With<SettingsPanel>>) {
+    for e in panel_q.iter() {
+        cmd.entity(e).despawn_recursive();
+    }
+}
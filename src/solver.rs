@@ -0,0 +1,109 @@
+use crate::{
+    board::BoardLayout,
+    map::WorldMap,
+    piece::{get_opposite_side_index, HexBlueprints},
+};
+use bevy::utils::HashSet;
+use hexx::Hex;
+use std::collections::VecDeque;
+
+/// Which pairs of sides can be joined by a single route hex.
+type SidePairs = [[bool; 6]; 6];
+
+fn side_pairs(blueprints: &HexBlueprints) -> SidePairs {
+    let mut pairs = [[false; 6]; 6];
+
+    for connections in blueprints.spawnable_connections() {
+        // the pairs are rotation invariant, so every rotation of the blueprint is covered
+        for rotation in 0..6 {
+            for a in (0..6).filter(|side| connections[*side]) {
+                for b in (0..6).filter(|side| *side != a && connections[*side]) {
+                    pairs[(a + rotation) % 6][(b + rotation) % 6] = true;
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+fn side_neighbour(hex: Hex, side: usize) -> Hex {
+    hex + Hex::new(1, -1).rotate_cw(side as u32)
+}
+
+/// Checks whether all `houses` can be connected by placing route hexes on the `empty` hexes.
+///
+/// The lot restriction is ignored, so any blueprint can go on any empty hex in any rotation.
+/// Routes are allowed to share hexes, which assumes the shared hex can carry all the sides the routes need.
+pub fn houses_connectable(
+    empty: &HashSet<Hex>,
+    houses: &[Hex],
+    blueprints: &HexBlueprints,
+) -> bool {
+    let Some(start_house) = houses.first() else {
+        return true;
+    };
+
+    let pairs = side_pairs(blueprints);
+    let house_set: HashSet<_> = houses.iter().copied().collect();
+    let mut reached = HashSet::with_capacity(houses.len());
+    reached.insert(*start_house);
+
+    // a state is an empty hex together with the side the route enters it from
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<_> = (0..6)
+        .map(|side| {
+            (
+                side_neighbour(*start_house, side),
+                get_opposite_side_index(side),
+            )
+        })
+        .filter(|(hex, _)| empty.contains(hex))
+        .collect();
+
+    while let Some((hex, entry_side)) = queue.pop_front() {
+        if !visited.insert((hex, entry_side)) {
+            continue;
+        }
+
+        for exit_side in (0..6).filter(|side| pairs[entry_side][*side]) {
+            let target = side_neighbour(hex, exit_side);
+
+            if house_set.contains(&target) {
+                reached.insert(target);
+            } else if empty.contains(&target) {
+                queue.push_back((target, get_opposite_side_index(exit_side)));
+            }
+        }
+
+        if reached.len() == house_set.len() {
+            return true;
+        }
+    }
+
+    reached.len() == house_set.len()
+}
+
+/// Checks whether all houses of a freshly generated board can be connected.
+pub fn is_board_solvable(board: &BoardLayout, blueprints: &HexBlueprints) -> bool {
+    houses_connectable(
+        &board.playable.iter().copied().collect(),
+        &board.houses,
+        blueprints,
+    )
+}
+
+/// Checks whether all houses of the map can be connected through the empty hexes alone.
+/// Placed route hexes are treated as obstacles, so it's no proof the hive can't be finished
+/// by routing through them.
+pub fn empty_hexes_connect_houses(map: &WorldMap, blueprints: &HexBlueprints) -> bool {
+    let empty = map
+        .hexes
+        .iter()
+        .filter(|(_, map_hex)| map_hex.placed_hex_e.is_none())
+        .map(|(hex, _)| *hex)
+        .collect();
+    let houses: Vec<_> = map.houses().collect();
+
+    houses_connectable(&empty, &houses, blueprints)
+}
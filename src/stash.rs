@@ -0,0 +1,212 @@
+use crate::{
+    animation::{delay_tween, get_scale_tween, get_translation_anim},
+    hex_cursor::HexCursor,
+    history::PlacementHistory,
+    input::GameAction,
+    loading::TextureAssets,
+    map::{WorldLayout, WorldMap},
+    map_completion::CompletedMap,
+    pause::PauseState,
+    piece::{lot_position, Carried, HoveredPiece, InitialPosition, LotSlot, Piece},
+    reset::{Resettable, ResettableGrid},
+    score::UpdateTimerEv,
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_tweening::{Animator, EaseFunction};
+use hexx::Hex;
+use leafwing_input_manager::prelude::*;
+
+/// Lot slot of the stashed piece, above the lot ones so the hex cursor cycles to it last.
+pub const STASH_SLOT: usize = 3;
+
+/// Piece parked in the hold slot.
+/// It isn't part of the lot, so it's kept across lots and hives until it gets placed.
+#[derive(Component)]
+pub struct Stashed;
+
+/// Marks the empty hold slot tile.
+#[derive(Component)]
+struct StashSlot;
+
+#[derive(Resource)]
+pub struct StashSettings {
+    /// Seconds taken off the timer for swapping a lot piece with the stashed one.
+    /// Parking a piece in the empty slot is free.
+    pub swap_time_cost: f32,
+}
+
+impl Default for StashSettings {
+    fn default() -> Self {
+        Self { swap_time_cost: 5. }
+    }
+}
+
+/// Parks the lot piece in the hold slot, the stashed piece (if any) takes its place in the lot.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct StashPieceRequest {
+    pub piece: Entity,
+}
+
+pub struct StashPlugin;
+impl Plugin for StashPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StashSettings>()
+            .add_event::<StashPieceRequest>()
+            .add_systems(
+                Update,
+                (
+                    place_stash.run_if(resource_added::<WorldMap>()),
+                    request_stash,
+                    stash_piece.after(request_stash),
+                )
+                    .distributive_run_if(
+                        in_state(GameState::Game)
+                            .and_then(resource_exists::<WorldMap>())
+                            .and_then(in_state(PauseState::Running))
+                            .and_then(not(resource_exists::<CompletedMap>())),
+                    ),
+            );
+    }
+}
+
+fn stash_position(map_layout: &WorldLayout, map: &WorldMap) -> Vec3 {
+    // the camera is shifted towards the lot, so there's less room on the left side of the board
+    map_layout
+        .hex_to_world_pos(Hex::new(-(map.map_radius as i32 + 2), 0))
+        .extend(1.)
+}
+
+/// Spawns the slot tile of a new board and moves the stashed piece next to it.
+fn place_stash(
+    mut cmd: Commands,
+    map: Res<WorldMap>,
+    map_layout: Res<WorldLayout>,
+    sprites: Res<TextureAssets>,
+    mut stashed_q: Query<(Entity, &mut InitialPosition), With<Stashed>>,
+) {
+    let pos = stash_position(&map_layout, &map);
+
+    cmd.spawn((
+        SpriteSheetBundle {
+            transform: Transform::from_translation(pos.truncate().extend(0.05))
+                .with_scale(Vec2::ZERO.extend(1.)),
+            sprite: TextureAtlasSprite {
+                index: 12,
+                color: Color::WHITE.with_a(0.5),
+                ..default()
+            },
+            texture_atlas: sprites.tiles.clone(),
+            ..default()
+        },
+        Animator::new(delay_tween(
+            get_scale_tween(None, Vec3::ONE, 350, EaseFunction::BackOut),
+            900,
+        )),
+        StashSlot,
+        ResettableGrid,
+    ));
+
+    for (e, mut initial_pos) in stashed_q.iter_mut() {
+        initial_pos.0 = pos;
+        cmd.entity(e).try_insert(get_translation_anim(
+            None,
+            pos,
+            350,
+            EaseFunction::QuadraticOut,
+        ));
+    }
+}
+
+/// Stashes the held piece, the hovered one or the one selected by the hex cursor.
+fn request_stash(
+    input: Res<ActionState<GameAction>>,
+    cursor: Res<HexCursor>,
+    hovered: Res<HoveredPiece>,
+    lot_q: Query<(Entity, &LotSlot), With<Piece>>,
+    mut ev_w: EventWriter<StashPieceRequest>,
+) {
+    if !input.just_pressed(GameAction::Stash) {
+        return;
+    }
+
+    let piece = cursor
+        .held
+        .or_else(|| hovered.0.as_ref().map(|hovered| hovered.piece_e))
+        .or_else(|| {
+            lot_q
+                .iter()
+                .find(|(_, slot)| cursor.active && slot.0 == cursor.slot)
+                .map(|(e, _)| e)
+        });
+
+    if let Some(piece) = piece {
+        ev_w.send(StashPieceRequest { piece });
+    }
+}
+
+fn stash_piece(
+    mut cmd: Commands,
+    mut ev_r: EventReader<StashPieceRequest>,
+    mut timer_ev_w: EventWriter<UpdateTimerEv>,
+    mut lot_q: Query<(&mut InitialPosition, &LotSlot), (With<Piece>, Without<Stashed>)>,
+    stashed_q: Query<(Entity, &Piece), With<Stashed>>,
+    mut history: ResMut<PlacementHistory>,
+    mut cursor: ResMut<HexCursor>,
+    settings: Res<StashSettings>,
+    map: Res<WorldMap>,
+    map_layout: Res<WorldLayout>,
+) {
+    // the stash can't be queried again until the commands are applied, so only one stash per frame
+    let Some(ev) = ev_r.read().last().copied() else {
+        return;
+    };
+
+    // the stashed piece itself or placed pieces can't be stashed
+    let Ok((mut initial_pos, slot)) = lot_q.get_mut(ev.piece) else {
+        return;
+    };
+
+    let lot_slot = *slot;
+    initial_pos.0 = stash_position(&map_layout, &map);
+
+    if cursor.held == Some(ev.piece) {
+        cursor.held = None;
+    }
+
+    cmd.entity(ev.piece)
+        .remove::<(Carried, ResettableGrid)>()
+        .try_insert((
+            Stashed,
+            Resettable,
+            LotSlot(STASH_SLOT),
+            get_translation_anim(None, initial_pos.0, 250, EaseFunction::QuadraticOut),
+        ));
+
+    if let Ok((stashed_e, stashed)) = stashed_q.get_single() {
+        // centred on the slot the same way the lot pieces are
+        let lot_pos = lot_position(
+            &map_layout,
+            &map,
+            lot_slot.0,
+            stashed.hexes().map(|(hex, _)| *hex),
+        );
+
+        cmd.entity(stashed_e)
+            .remove::<(Stashed, Resettable)>()
+            .try_insert((
+                ResettableGrid,
+                lot_slot,
+                InitialPosition(lot_pos),
+                get_translation_anim(None, lot_pos, 250, EaseFunction::QuadraticOut),
+            ));
+
+        if settings.swap_time_cost > 0. {
+            timer_ev_w.send(UpdateTimerEv(-settings.swap_time_cost));
+        }
+    }
+
+    // the lot got rearranged, so the placements so far can't be undone anymore
+    history.undo.clear();
+    history.redo.clear();
+}
@@ -0,0 +1,105 @@
+use bevy::{prelude::*, utils::HashMap};
+use std::path::PathBuf;
+
+/// Small key-value store for the data that outlives a session.
+/// Failing to save only gets logged, the game keeps going without it.
+pub trait KeyValueStore: Send + Sync {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str);
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct Storage(pub Box<dyn KeyValueStore>);
+
+impl Default for Storage {
+    fn default() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Self(Box::new(FileStore::new(FileStore::default_dir())));
+
+        #[cfg(target_arch = "wasm32")]
+        return Self(Box::new(LocalStore));
+    }
+}
+
+/// Keeps the values in memory only, used by tests.
+#[derive(Debug, Default)]
+pub struct MemoryStore(pub HashMap<String, String>);
+
+impl KeyValueStore for MemoryStore {
+    fn load(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        self.0.insert(key.to_string(), value.to_string());
+    }
+}
+
+/// Stores every key as a file in a directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Platform data directory, falling back to the working directory.
+    pub fn default_dir() -> PathBuf {
+        let data_dir = std::env::var_os("APPDATA")
+            .or_else(|| std::env::var_os("XDG_DATA_HOME"))
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            });
+
+        match data_dir {
+            Some(dir) => dir.join("bee_trails"),
+            None => PathBuf::from("saves"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl KeyValueStore for FileStore {
+    fn load(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.dir.join(key)).ok()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        if let Err(e) = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.dir.join(key), value))
+        {
+            warn!("Failed to save {key} to {:?}: {e}", self.dir);
+        }
+    }
+}
+
+/// Stores the values in the browser's LocalStorage.
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStore;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStore {
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl KeyValueStore for LocalStore {
+    fn load(&self, key: &str) -> Option<String> {
+        Self::storage()?.get_item(key).ok()?
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        let saved = Self::storage().map_or(false, |storage| storage.set_item(key, value).is_ok());
+
+        if !saved {
+            warn!("Failed to save {key} to the local storage");
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::{game_loop::free_hex, TestGame};
+use crate::{
+    audio::{music_speed, Sfx, MUSIC_RUSH_SECS, TIMER_WARNING_SECS},
+    GameState,
+};
+use bevy::{prelude::*, utils::HashSet};
+use hexx::Hex;
+
+/// Sound effects that are playing.
+fn playing(game: &mut TestGame, sfx: Sfx) -> HashSet<Entity> {
+    game.app
+        .world
+        .query::<(Entity, &Sfx)>()
+        .iter(&game.app.world)
+        .filter(|(_, s)| **s == sfx)
+        .map(|(e, _)| e)
+        .collect()
+}
+
+#[test]
+fn piece_actions_play_sfx() {
+    let mut game = TestGame::start();
+
+    game.rotate_piece(0, true);
+    game.update();
+    assert_eq!(playing(&mut game, Sfx::Rotate).len(), 1);
+
+    game.place_piece(0, Hex::new(100, 100), 0);
+    game.update();
+    assert_eq!(playing(&mut game, Sfx::InvalidDrop).len(), 1);
+    assert!(playing(&mut game, Sfx::Snap).is_empty());
+
+    let piece_e = game.lot()[1];
+    let hex = free_hex(&game, piece_e);
+    game.place_piece(1, hex, 0);
+    game.update();
+    assert_eq!(playing(&mut game, Sfx::Snap).len(), 1);
+
+    // the effects are cleaned up even though nothing plays them
+    game.advance(1.);
+    assert!(playing(&mut game, Sfx::Snap).is_empty());
+}
+
+#[test]
+fn running_out_of_time_ticks_every_second() {
+    let mut game = TestGame::start();
+    let mut warnings = HashSet::new();
+
+    game.advance(game.remaining_secs() - TIMER_WARNING_SECS as f32 - 1.);
+    assert!(playing(&mut game, Sfx::TimerWarning).is_empty());
+
+    while game.state() == GameState::Game {
+        game.update();
+        warnings.extend(playing(&mut game, Sfx::TimerWarning));
+    }
+    game.update();
+
+    assert_eq!(warnings.len(), TIMER_WARNING_SECS as usize);
+    assert_eq!(playing(&mut game, Sfx::GameOver).len(), 1);
+}
+
+#[test]
+fn music_picks_up_as_timer_runs_low() {
+    assert_eq!(music_speed(None), 1.);
+    assert_eq!(music_speed(Some(MUSIC_RUSH_SECS + 60.)), 1.);
+    assert!(music_speed(Some(MUSIC_RUSH_SECS / 2.)) > 1.);
+    assert!(music_speed(Some(0.)) > music_speed(Some(MUSIC_RUSH_SECS / 2.)));
+}
@@ -0,0 +1,81 @@
+use super::TestGame;
+use crate::{
+    board::BoardLayout,
+    daily::{daily_seed, hive_grid, today, DailyResults, DailyRun},
+    game_mode::GameMode,
+    high_scores::HighScores,
+    map::BoardSeed,
+    reset::RegisteredSystems,
+    GameState,
+};
+
+#[test]
+fn daily_seed_changes_every_day() {
+    assert_eq!(daily_seed(20_000), daily_seed(20_000));
+    assert_ne!(daily_seed(20_000), daily_seed(20_001));
+}
+
+#[test]
+fn daily_runs_share_the_board() {
+    let games = [
+        TestGame::start_in(GameMode::Daily),
+        TestGame::start_in(GameMode::Daily),
+    ];
+    let seeds: Vec<_> = games
+        .iter()
+        .map(|game| game.app.world.resource::<BoardSeed>().seed)
+        .collect();
+
+    assert_eq!(seeds, vec![daily_seed(today()); 2]);
+}
+
+#[test]
+fn only_first_daily_attempt_is_scored() {
+    let mut game = TestGame::start_in(GameMode::Daily);
+    assert!(game.app.world.resource::<DailyRun>().scored);
+    assert!(game.remaining_secs() > 149.);
+
+    // a run restarted from the pause menu never reached the game over, so it's not an attempt
+    let systems = game.app.world.resource::<RegisteredSystems>();
+    let (pause, restart) = (systems.pause, systems.restart);
+    game.app.world.run_system(pause).unwrap();
+    game.update();
+    game.app.world.run_system(restart).unwrap();
+    game.update_until(|game| game.lot().len() == 3);
+
+    assert!(!game.app.world.resource::<DailyResults>().played(today()));
+    assert!(game.app.world.resource::<DailyRun>().scored);
+
+    game.set_state(GameState::GameOver);
+
+    let results = game.app.world.resource::<DailyResults>();
+    assert!(results.played(today()));
+    assert!(results
+        .last
+        .as_ref()
+        .unwrap()
+        .share
+        .starts_with("Bee Trails Daily"));
+    assert_eq!(game.app.world.resource::<HighScores>().entries.len(), 1);
+
+    game.set_state(GameState::Game);
+    assert!(!game.app.world.resource::<DailyRun>().scored);
+    game.set_state(GameState::GameOver);
+
+    assert_eq!(game.app.world.resource::<HighScores>().entries.len(), 1);
+}
+
+#[test]
+fn hive_grid_has_row_per_hex_row() {
+    let game = TestGame::start_in(GameMode::Daily);
+    let board = game.app.world.resource::<BoardLayout>();
+    let radius = board.hexes().map(|hex| hex.ulength()).max().unwrap();
+
+    let grid = hive_grid(game.map(), board);
+
+    assert_eq!(grid.lines().count(), radius as usize * 2 + 1);
+    assert_eq!(
+        grid.chars().filter(|c| *c == '🏠').count(),
+        game.map().house_count()
+    );
+}
@@ -0,0 +1,64 @@
+use super::{load_difficulty, TestGame};
+use crate::{
+    board::generate_board,
+    difficulty::{DifficultyConfig, DifficultyError},
+    piece::HexBlueprints,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn tiers_follow_level() {
+    let config = load_difficulty();
+
+    assert_eq!(config.tier(0).map_radius, 2);
+    assert!(!config.tier(0).houses_outside_grid);
+    assert!(config.tier(1).houses_outside_grid);
+    assert_eq!(config.tier(4).map_radius, 3);
+    assert_eq!(config.tier(7).map_radius, 4);
+    assert_eq!(config.tier(100).map_radius, 5);
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let board = generate_board(config.tier(8), &mut rng);
+
+    assert_eq!(board.map_radius, 5);
+    assert!(!board.blocked.is_empty());
+}
+
+#[test]
+fn blueprint_weights_follow_level() {
+    let mut config = load_difficulty();
+    let blueprint_count = config.blueprints.len();
+    config.blueprints[blueprint_count - 1].weights = vec![(3, 5)];
+
+    assert_eq!(config.blueprints[blueprint_count - 1].weight(2), 0);
+    assert_eq!(config.blueprints[blueprint_count - 1].weight(3), 5);
+    assert_eq!(
+        HexBlueprints::new(&config)
+            .unwrap()
+            .spawnable_connections()
+            .count(),
+        blueprint_count
+    );
+}
+
+#[test]
+fn invalid_config_is_rejected() {
+    let mut config = load_difficulty();
+    config.tiers[0].piece_size_weights = vec![0, 0];
+
+    assert!(matches!(
+        HexBlueprints::new(&config),
+        Err(DifficultyError::Weights { level: 0, .. })
+    ));
+    assert!(matches!(
+        DifficultyConfig::from_ron("(blueprints: [], tiers: [])"),
+        Err(DifficultyError::NoTiers)
+    ));
+}
+
+#[test]
+fn game_uses_loaded_config() {
+    let game = TestGame::start();
+
+    assert_eq!(game.map().map_radius, load_difficulty().tier(0).map_radius);
+}
@@ -0,0 +1,311 @@
+use super::TestGame;
+use crate::{
+    board::BoardLayout,
+    history::PlacementHistory,
+    map::{BoardSeed, WorldMap},
+    piece::{HiddenPiece, Piece, PlacePieceRequest},
+    score::Streak,
+    scoring::{ScoreBreakdown, DEAD_ENDS},
+    GameState,
+};
+use bevy::prelude::*;
+use hexx::Hex;
+
+fn placed_hex_count(game: &TestGame) -> usize {
+    game.map()
+        .hexes
+        .values()
+        .filter(|map_hex| map_hex.placed_hex_e.is_some())
+        .count()
+}
+
+/// Hex the piece fits on without touching a house, so placing it can't complete the hive.
+pub fn free_hex(game: &TestGame, piece_e: Entity) -> Hex {
+    let map = game.map();
+    let piece = game.piece(piece_e);
+
+    map.hexes
+        .keys()
+        .copied()
+        .find(|hex| {
+            piece.fits(map, *hex)
+                && piece.hexes().all(|(offset, _)| {
+                    map.houses()
+                        .all(|house| house.unsigned_distance_to(*hex + *offset) > 1)
+                })
+        })
+        .expect("The board has room for a piece")
+}
+
+#[test]
+fn starting_spawns_board_and_lot() {
+    let game = TestGame::start();
+
+    assert!(game.map().house_count() >= 2);
+    assert_eq!(game.score(), 0);
+    assert_eq!(game.level(), 0);
+    assert!(game.remaining_secs() > 149.);
+}
+
+#[test]
+fn skipping_rerolls_board_and_costs_time() {
+    let mut game = TestGame::start();
+    game.app.world.resource_mut::<Streak>().count = 3;
+    let remaining = game.remaining_secs();
+
+    game.skip();
+
+    assert_eq!(game.app.world.resource::<BoardSeed>().reroll, 1);
+    assert_eq!(game.app.world.resource::<Streak>().count, 0);
+    assert!(game.remaining_secs() < remaining - 4.9);
+}
+
+#[test]
+fn invalid_drop_keeps_piece_in_lot() {
+    let mut game = TestGame::start();
+    let placed = placed_hex_count(&game);
+    let piece_e = game.lot()[0];
+
+    game.place_piece(0, Hex::new(100, 100), 0);
+
+    assert!(game.app.world.get::<Piece>(piece_e).is_some());
+    assert_eq!(game.lot().len(), 3);
+    assert_eq!(placed_hex_count(&game), placed);
+}
+
+/// Board around a route hex of the lot, with a house on both ends of the route.
+/// Placing the piece at the index with its origin at the hex completes the hive.
+pub fn completable_board(game: &TestGame) -> (usize, Hex, BoardLayout) {
+    game.lot()
+        .iter()
+        .enumerate()
+        .find_map(|(i, piece_e)| {
+            let piece = game.piece(*piece_e);
+
+            piece.hexes().find_map(|(offset, data)| {
+                let connections = data.connections?;
+                let mut sides = (0..6).filter(|side| connections[*side]);
+                let houses: Vec<_> = [sides.next()?, sides.next()?]
+                    .iter()
+                    .map(|side| Hex::new(1, -1).rotate_cw(*side as u32))
+                    .collect();
+                let origin = Hex::ZERO - *offset;
+                let playable: Vec<_> = piece.hexes().map(|(hex, _)| origin + *hex).collect();
+
+                if houses.iter().any(|house| playable.contains(house)) {
+                    return None;
+                }
+
+                Some((
+                    i,
+                    origin,
+                    BoardLayout {
+                        map_radius: 0,
+                        playable,
+                        houses,
+                        blocked: Vec::new(),
+                        routes: Vec::new(),
+                    },
+                ))
+            })
+        })
+        .expect("A lot always contains a route hex")
+}
+
+#[test]
+fn connecting_all_houses_completes_hive() {
+    let mut game = TestGame::start();
+    let (index, origin, board) = completable_board(&game);
+
+    game.set_board(board);
+    assert_eq!(game.connectivity().connected_count(), 0);
+    // the hive is multiplied by the streak it started with
+    let multiplier = game.app.world.resource::<Streak>().multiplier();
+
+    game.place_piece(index, origin, 0);
+    game.update();
+    assert_eq!(game.connectivity().connected_count(), 2);
+
+    assert!(
+        game.completed_map().is_some(),
+        "All houses should be connected"
+    );
+    let breakdown = game.app.world.resource::<ScoreBreakdown>();
+    let dead_ends = breakdown.points(DEAD_ENDS);
+    let gained = breakdown.total() - dead_ends;
+    let streak = game.app.world.resource::<Streak>();
+    assert_eq!(streak.count, if dead_ends == 0 { 2 } else { 0 });
+    let gained = (gained as f32 * multiplier).round() as i32;
+
+    game.advance(5.);
+
+    assert_eq!(game.level(), 1);
+    assert_eq!(game.score(), (gained + dead_ends).max(0) as u32);
+    assert!(game.completed_map().is_none());
+}
+
+#[test]
+fn running_out_of_time_ends_game() {
+    let mut game = TestGame::start();
+
+    game.advance(151.);
+
+    assert_eq!(game.state(), GameState::GameOver);
+}
+
+#[test]
+fn rotating_piece_rotates_hexes_and_connections() {
+    let mut game = TestGame::start();
+    let piece_e = game.lot()[0];
+    let hexes_before: Vec<_> = game
+        .piece(piece_e)
+        .hexes()
+        .map(|(hex, data)| (*hex, data.entity, data.connections))
+        .collect();
+
+    game.rotate_piece(0, true);
+
+    let piece = game.piece(piece_e);
+    for (hex, entity, connections) in hexes_before {
+        let (rotated_hex, data) = piece
+            .hexes()
+            .find(|(_, data)| data.entity == entity)
+            .unwrap();
+
+        assert_eq!(*rotated_hex, hex.cw_around(Hex::ZERO));
+        assert_eq!(
+            data.connections,
+            connections.map(|mut sides| {
+                sides.rotate_right(1);
+                sides
+            })
+        );
+    }
+}
+
+#[test]
+fn undoing_placement_restores_board_and_lot() {
+    let mut game = TestGame::start();
+    let placed = placed_hex_count(&game);
+    let lot = game.lot();
+    let hex = free_hex(&game, lot[0]);
+
+    game.place_piece(0, hex, 0);
+    let placed_piece = placed_hex_count(&game);
+    let remaining = game.remaining_secs();
+    game.map().check_consistency().unwrap();
+
+    game.undo();
+
+    assert_eq!(placed_hex_count(&game), placed);
+    assert!(game.map().owned_edges(hex).is_empty());
+    game.map().check_consistency().unwrap();
+    assert_eq!(game.lot().len(), 3);
+    assert!(game.app.world.get::<Piece>(lot[0]).is_some());
+    assert!(game.remaining_secs() < remaining - 1.9);
+
+    game.redo();
+
+    assert_eq!(placed_hex_count(&game), placed_piece);
+    assert!(game.app.world.get::<Piece>(lot[0]).is_none());
+    game.map().check_consistency().unwrap();
+}
+
+#[test]
+fn undoing_lot_refresh_offers_previous_lot() {
+    let mut game = TestGame::start();
+    let lot = game.lot();
+
+    for _ in 0..2 {
+        let piece_e = game.lot()[0];
+        let hex = free_hex(&game, piece_e);
+        game.app.world.send_event(PlacePieceRequest {
+            piece: piece_e,
+            hex,
+        });
+        game.update();
+    }
+
+    game.update_until(|game| game.lot().len() == 3);
+    let next_lot = game.lot();
+    assert!(next_lot.iter().all(|e| !lot.contains(e)));
+
+    game.undo();
+
+    let mut restored = game.lot();
+    restored.sort();
+    let mut expected = vec![lot[1], lot[2]];
+    expected.sort();
+    assert_eq!(restored, expected);
+
+    game.redo();
+
+    let mut redone = game.lot();
+    redone.sort();
+    let mut expected = next_lot;
+    expected.sort();
+    assert_eq!(redone, expected);
+}
+
+#[test]
+fn placing_after_undone_lot_refresh_drops_hidden_lot() {
+    let mut game = TestGame::start();
+
+    for _ in 0..2 {
+        let piece_e = game.lot()[0];
+        let hex = free_hex(&game, piece_e);
+        game.place_piece(0, hex, 0);
+    }
+
+    game.update_until(|game| game.lot().len() == 3);
+    let next_lot = game.lot();
+    game.undo();
+
+    // placing the other piece, the undone one would count as a redo
+    let undone = game.app.world.resource::<PlacementHistory>().redo[0].piece;
+    let lot = game.lot();
+    let index = lot.iter().position(|e| *e != undone).unwrap();
+    let hex = free_hex(&game, lot[index]);
+    game.place_piece(index, hex, 0);
+    game.update();
+
+    assert!(next_lot
+        .iter()
+        .all(|e| game.app.world.get_entity(*e).is_none()));
+    // only the leftover of the lot that's been used up stays hidden, undoing can offer it again
+    let discarded: Vec<_> = game
+        .app
+        .world
+        .resource::<PlacementHistory>()
+        .undo
+        .iter()
+        .flat_map(|placement| placement.discarded.clone())
+        .collect();
+    let hidden: Vec<_> = game
+        .app
+        .world
+        .query_filtered::<Entity, With<HiddenPiece>>()
+        .iter(&game.app.world)
+        .collect();
+    assert!(hidden.iter().all(|e| discarded.contains(e)));
+}
+
+#[test]
+fn houses_and_blocked_hexes_cannot_be_removed() {
+    let (house, blocked) = (Hex::new(2, 0), Hex::new(0, 1));
+    let mut map = WorldMap::new(
+        &BoardLayout {
+            map_radius: 2,
+            playable: vec![Hex::ZERO],
+            houses: vec![house, Hex::new(-2, 0)],
+            blocked: vec![blocked],
+            routes: Vec::new(),
+        },
+        |_| Entity::PLACEHOLDER,
+    );
+
+    for hex in [house, blocked] {
+        assert_eq!(map.remove_hex(hex), None);
+        assert!(map.hexes[&hex].placed_hex_e.is_some());
+    }
+}
@@ -0,0 +1,70 @@
+use super::TestGame;
+use crate::{
+    game_mode::{GameMode, RunClock, MARATHON_PAR_SECS},
+    reset::RegisteredSystems,
+    score::{GameTimer, Score, UpdateScoreEv},
+    GameState,
+};
+use hexx::Hex;
+
+#[test]
+fn modes_cycle_through_all_of_them() {
+    let mut mode = GameMode::Timed;
+
+    for expected in [
+        GameMode::Zen,
+        GameMode::Marathon,
+        GameMode::Puzzle,
+        GameMode::Daily,
+        GameMode::Timed,
+    ] {
+        mode = mode.next();
+        assert_eq!(mode, expected);
+    }
+}
+
+#[test]
+fn zen_mode_never_ends() {
+    let mut game = TestGame::start_in(GameMode::Zen);
+
+    game.advance(200.);
+
+    assert_eq!(game.state(), GameState::Game);
+    assert!(game.app.world.get_resource::<GameTimer>().is_none());
+    assert!(game.app.world.resource::<RunClock>().elapsed_secs() > 199.);
+}
+
+#[test]
+fn marathon_is_scored_by_time() {
+    let mut game = TestGame::start_in(GameMode::Marathon);
+    game.app.world.send_event(UpdateScoreEv(50));
+    game.advance(10.);
+    assert_eq!(game.score(), 0);
+
+    let end_run = game.app.world.resource::<RegisteredSystems>().end_run;
+    game.app.world.run_system(end_run).unwrap();
+    let secs = game.app.world.resource::<RunClock>().elapsed_secs();
+    game.update();
+
+    assert_eq!(game.state(), GameState::GameOver);
+    assert_eq!(game.score(), (MARATHON_PAR_SECS - secs) as u32);
+}
+
+#[test]
+fn solving_puzzle_moves_to_next_one() {
+    let mut game = TestGame::start_in(GameMode::Puzzle);
+    assert_eq!(game.map().house_count(), 2);
+
+    // the straight piece fills the left half, the loose one has to be turned to fit the right one
+    game.place_piece(0, Hex::new(-1, 0), 0);
+    game.place_piece(0, Hex::new(1, 0), 2);
+
+    let completed_map = game.completed_map().expect("The puzzle should be solved");
+    assert!(completed_map.dead_ends.is_empty());
+
+    game.advance(5.);
+
+    assert_eq!(game.state(), GameState::Game);
+    assert_eq!(game.level(), 1);
+    assert_eq!(game.map().house_count(), 3);
+}
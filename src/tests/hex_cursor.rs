@@ -0,0 +1,118 @@
+use super::TestGame;
+use crate::{
+    board::BoardLayout,
+    hex_cursor::HexCursor,
+    input::GameAction,
+    piece::{Carried, InitialPosition, LotSlot, Piece},
+};
+use bevy::prelude::*;
+use hexx::{shapes, Hex};
+use leafwing_input_manager::{axislike::DualAxisData, prelude::*};
+
+/// Open board every lot piece fits on with its origin at the centre.
+fn open_board() -> BoardLayout {
+    BoardLayout {
+        map_radius: 3,
+        playable: shapes::hexagon(Hex::ZERO, 3).collect(),
+        houses: vec![Hex::new(-5, 0), Hex::new(5, 0)],
+        blocked: Vec::new(),
+        routes: Vec::new(),
+    }
+}
+
+/// Presses the action for a single frame.
+fn press(game: &mut TestGame, action: GameAction) {
+    press_dir(game, action, Vec2::ZERO);
+}
+
+fn press_dir(game: &mut TestGame, action: GameAction, dir: Vec2) {
+    let mut input = game.app.world.resource_mut::<ActionState<GameAction>>();
+    input.press(action);
+    input.action_data_mut(action).axis_pair = Some(DualAxisData::from_xy(dir));
+    game.update();
+
+    game.app
+        .world
+        .resource_mut::<ActionState<GameAction>>()
+        .release(action);
+    game.update();
+}
+
+fn cursor(game: &TestGame) -> &HexCursor {
+    game.app.world.resource::<HexCursor>()
+}
+
+fn slot(game: &TestGame, piece_e: Entity) -> usize {
+    game.app.world.get::<LotSlot>(piece_e).unwrap().0
+}
+
+#[test]
+fn cursor_cycles_over_the_lot() {
+    let mut game = TestGame::start();
+    let mut slots: Vec<_> = game.lot().into_iter().map(|e| slot(&game, e)).collect();
+    slots.sort();
+
+    // the first press only shows the selection
+    press_dir(&mut game, GameAction::MoveDir, Vec2::Y);
+    assert!(cursor(&game).active);
+    assert_eq!(cursor(&game).slot, slots[0]);
+
+    press_dir(&mut game, GameAction::MoveDir, Vec2::Y);
+    assert_eq!(cursor(&game).slot, slots[1]);
+
+    press_dir(&mut game, GameAction::MoveDir, Vec2::NEG_Y);
+    press_dir(&mut game, GameAction::MoveDir, Vec2::NEG_Y);
+    assert_eq!(cursor(&game).slot, slots[2]);
+}
+
+#[test]
+fn cursor_picks_moves_and_drops_piece() {
+    let mut game = TestGame::start();
+    game.set_board(open_board());
+
+    press(&mut game, GameAction::Move);
+    let piece_e = cursor(&game).held.expect("A lot piece should be held");
+    assert!(game.app.world.get::<Carried>(piece_e).is_some());
+    assert_eq!(cursor(&game).hex, Hex::ZERO);
+
+    press_dir(&mut game, GameAction::MoveDir, Vec2::X);
+    let hex = cursor(&game).hex;
+    assert_eq!(hex.unsigned_distance_to(Hex::ZERO), 1);
+    assert_eq!(game.piece(piece_e).target_hex(), Some(hex));
+
+    press(&mut game, GameAction::Move);
+
+    assert!(cursor(&game).held.is_none());
+    assert!(game.app.world.get::<Piece>(piece_e).is_none());
+    assert!(game.map().hexes[&hex].placed_hex_e.is_some());
+}
+
+#[test]
+fn dropping_over_invalid_hex_returns_piece_to_lot() {
+    let mut game = TestGame::start();
+    game.set_board(open_board());
+
+    // the centre gets taken by the first piece
+    press(&mut game, GameAction::Move);
+    press(&mut game, GameAction::Move);
+    assert!(game.map().hexes[&Hex::ZERO].placed_hex_e.is_some());
+
+    press(&mut game, GameAction::Move);
+    let piece_e = cursor(&game).held.expect("A lot piece should be held");
+    assert_eq!(game.piece(piece_e).target_hex(), None);
+
+    press(&mut game, GameAction::Move);
+    game.advance(0.5);
+
+    assert!(cursor(&game).held.is_none());
+    assert!(game.app.world.get::<Carried>(piece_e).is_none());
+    assert!(game.lot().contains(&piece_e));
+    let initial_pos = game.app.world.get::<InitialPosition>(piece_e).unwrap().0;
+    let translation = game
+        .app
+        .world
+        .get::<Transform>(piece_e)
+        .unwrap()
+        .translation;
+    assert!(translation.truncate().distance(initial_pos.truncate()) < 1.);
+}
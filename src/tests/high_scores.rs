@@ -0,0 +1,110 @@
+use super::TestGame;
+use crate::{
+    game_mode::GameMode,
+    high_scores::{HighScoreEntry, HighScores, LastRun, MAX_HIGH_SCORES},
+    score::Score,
+    storage::{MemoryStore, Storage},
+    GameState,
+};
+
+fn entry(score: u32) -> HighScoreEntry {
+    mode_entry(score, GameMode::Timed)
+}
+
+fn mode_entry(score: u32, mode: GameMode) -> HighScoreEntry {
+    HighScoreEntry {
+        score,
+        level: score / 10,
+        timestamp: 1_700_000_000,
+        seed: 42,
+        mode: mode.label().to_string(),
+    }
+}
+
+#[test]
+fn high_scores_keep_best_entries_in_order() {
+    let mut high_scores = HighScores::default();
+
+    for score in 0..MAX_HIGH_SCORES as u32 {
+        high_scores.insert(entry(score * 10));
+    }
+
+    assert_eq!(high_scores.insert(entry(0)), None);
+    assert_eq!(high_scores.insert(entry(55)), Some(4));
+    assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+    assert_eq!(high_scores.best(GameMode::Timed).unwrap().score, 90);
+    assert!(high_scores
+        .entries
+        .windows(2)
+        .all(|pair| pair[0].score >= pair[1].score));
+    assert_eq!(entry(0).date(), "2023-11-14");
+}
+
+#[test]
+fn modes_are_ranked_separately() {
+    let mut high_scores = HighScores::default();
+
+    for score in 0..MAX_HIGH_SCORES as u32 {
+        high_scores.insert(entry(score * 10));
+    }
+
+    // marathon scores are seconds left, they don't compete with timed points
+    assert_eq!(
+        high_scores.insert(mode_entry(500, GameMode::Marathon)),
+        Some(0)
+    );
+    assert_eq!(
+        high_scores.insert(mode_entry(5, GameMode::Marathon)),
+        Some(1)
+    );
+    assert_eq!(high_scores.best(GameMode::Timed).unwrap().score, 90);
+    assert_eq!(
+        high_scores.ranking(GameMode::Timed).count(),
+        MAX_HIGH_SCORES
+    );
+    assert_eq!(high_scores.best(GameMode::Marathon).unwrap().score, 500);
+    assert!(high_scores.best(GameMode::Zen).is_none());
+
+    assert_eq!(
+        high_scores
+            .best(GameMode::Marathon)
+            .unwrap()
+            .result(GameMode::Marathon),
+        "TIME 100s"
+    );
+    assert_eq!(entry(90).result(GameMode::Timed), "90  LVL 9");
+}
+
+#[test]
+fn high_scores_round_trip_through_store() {
+    let mut store = MemoryStore::default();
+    let mut high_scores = HighScores::default();
+    high_scores.insert(entry(30));
+    high_scores.insert(entry(70));
+
+    high_scores.save(&mut store);
+
+    assert_eq!(HighScores::load(&store), high_scores);
+    assert_eq!(
+        HighScores::load(&MemoryStore::default()),
+        HighScores::default()
+    );
+}
+
+#[test]
+fn finishing_run_records_high_score() {
+    let mut game = TestGame::start();
+    game.app.world.resource_mut::<Score>().0 = 10;
+
+    // only runs that reach the game over are recorded
+    game.set_state(GameState::Tutorial);
+    assert!(game.app.world.resource::<HighScores>().entries.is_empty());
+
+    game.set_state(GameState::Game);
+    game.app.world.resource_mut::<Score>().0 = 25;
+    game.set_state(GameState::GameOver);
+
+    assert_eq!(game.app.world.resource::<LastRun>().rank, Some(0));
+    let stored = HighScores::load(game.app.world.resource::<Storage>().0.as_ref());
+    assert_eq!(stored.best(GameMode::Timed).map(|e| e.score), Some(25));
+}
@@ -0,0 +1,109 @@
+use super::{load_level, TestGame};
+use crate::{
+    level::{LevelAsset, LevelLoaderError},
+    level_editor::EditedLevel,
+    lot_queue::LOT_SIZE,
+    piece::HexBlueprints,
+};
+use hexx::Hex;
+
+#[test]
+fn level_file_describes_board() {
+    let level = LevelAsset::from_ron(
+        "(
+            hexes: [(0, 0), (1, 0), (2, 0), (0, 1)],
+            houses: [(-1, 0), (3, 0)],
+            blocked: [(0, 1)],
+            routes: [(hex: (1, 0), blueprint: Some(2))],
+        )",
+    )
+    .unwrap();
+
+    let board = level.board();
+
+    assert_eq!(board.map_radius, 2);
+    assert_eq!(
+        board.playable,
+        vec![Hex::ZERO, Hex::new(1, 0), Hex::new(2, 0)]
+    );
+    assert_eq!(board.houses, vec![Hex::new(-1, 0), Hex::new(3, 0)]);
+    assert_eq!(board.blocked, vec![Hex::new(0, 1)]);
+    assert_eq!(board.routes.len(), 1);
+    assert!(level.lots().is_none());
+}
+
+#[test]
+fn level_routes_are_placed_on_the_map() {
+    let mut game = TestGame::start();
+    let level = load_level("assets/levels/puzzle_2.level.ron");
+
+    game.set_board(level.board());
+
+    let map = game.map();
+    assert_eq!(map.house_count(), 3);
+    assert!(map.hexes[&Hex::new(0, -1)].placed_hex_e.is_some());
+    assert_eq!(game.connectivity().connected_count(), 0);
+    assert_eq!(level.lots().unwrap().lots().count(), 1);
+}
+
+#[test]
+fn levels_with_unknown_blueprints_are_rejected() {
+    let game = TestGame::start();
+    let blueprints = game.app.world.resource::<HexBlueprints>();
+    let unknown = blueprints.blueprint_count();
+    let level = LevelAsset::from_ron(&format!(
+        "(
+            hexes: [(0, 0), (1, 0)],
+            houses: [(-1, 0), (2, 0)],
+            lots: Some([[[(hex: (0, 0), blueprint: Some({unknown}))]]]),
+        )"
+    ))
+    .unwrap();
+
+    assert!(matches!(
+        level.validate(blueprints),
+        Err(LevelLoaderError::UnknownBlueprint(bp)) if bp == unknown
+    ));
+    assert!(load_level("assets/levels/puzzle_2.level.ron")
+        .validate(blueprints)
+        .is_ok());
+}
+
+#[test]
+fn levels_with_oversized_lots_are_rejected() {
+    let piece = "[(hex: (0, 0), blueprint: Some(0))]";
+    let level = |pieces: usize| {
+        LevelAsset::from_ron(&format!(
+            "(lots: Some([[{piece}], [{}]]))",
+            vec![piece; pieces].join(", ")
+        ))
+    };
+
+    assert!(level(LOT_SIZE).is_ok());
+    assert!(matches!(
+        level(LOT_SIZE + 1),
+        Err(LevelLoaderError::LotSize { lot: 1, pieces }) if pieces == LOT_SIZE + 1
+    ));
+}
+
+#[test]
+fn edited_level_round_trips() {
+    let level = load_level("assets/levels/puzzle_2.level.ron");
+    let sorted = |mut hexes: Vec<Hex>| {
+        hexes.sort_by_key(|hex| (hex.x, hex.y));
+        hexes
+    };
+
+    let edited = EditedLevel::from_level(&level).to_level();
+    let (board, edited_board) = (level.board(), edited.board());
+
+    assert_eq!(sorted(edited_board.playable), sorted(board.playable));
+    assert_eq!(sorted(edited_board.houses), sorted(board.houses));
+    assert_eq!(sorted(edited_board.blocked), sorted(board.blocked));
+    assert_eq!(edited_board.routes, board.routes);
+    assert_eq!(edited.lots, level.lots);
+    assert_eq!(
+        LevelAsset::from_ron(&ron::to_string(&edited).unwrap()).unwrap(),
+        edited
+    );
+}
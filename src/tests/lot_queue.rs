@@ -0,0 +1,89 @@
+use super::{game_loop::free_hex, TestGame};
+use crate::{
+    lot_queue::{LotQueue, LotSpec},
+    piece::{HexBlueprints, PieceHexSpec, PlacePieceRequest},
+};
+use hexx::Hex;
+
+type HexConnections = Vec<(Hex, Option<[bool; 6]>)>;
+
+fn sorted(mut hexes: HexConnections) -> HexConnections {
+    hexes.sort_by_key(|(hex, _)| (hex.x, hex.y));
+    hexes
+}
+
+fn lot_hexes(game: &mut TestGame) -> Vec<HexConnections> {
+    game.lot()
+        .into_iter()
+        .map(|e| {
+            sorted(
+                game.piece(e)
+                    .hexes()
+                    .map(|(hex, data)| (*hex, data.connections))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+fn spec_hexes(game: &TestGame, lot: &LotSpec) -> Vec<HexConnections> {
+    let blueprints = game.app.world.resource::<HexBlueprints>();
+
+    lot.iter()
+        .map(|piece| {
+            sorted(
+                piece
+                    .iter()
+                    .map(|hex| (hex.offset, blueprints.connections(hex)))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Places lot pieces away from the houses until the next lot gets offered.
+pub fn use_up_lot(game: &mut TestGame) {
+    for _ in 0..2 {
+        let piece = game.lot()[0];
+        let hex = free_hex(game, piece);
+        game.app.world.send_event(PlacePieceRequest { piece, hex });
+        game.update();
+    }
+
+    game.update_until(|game| game.lot().len() == 3);
+}
+
+#[test]
+fn next_lot_is_previewed() {
+    let mut game = TestGame::start();
+    let queue = game.app.world.resource::<LotQueue>();
+    assert!(!queue.is_fixed());
+    let next = spec_hexes(&game, queue.peek().unwrap());
+
+    use_up_lot(&mut game);
+
+    assert_eq!(lot_hexes(&mut game), next);
+    assert!(game.app.world.resource::<LotQueue>().peek().is_some());
+}
+
+#[test]
+fn fixed_queue_runs_out() {
+    let mut game = TestGame::start();
+    let lot: LotSpec = (0..3)
+        .map(|i| {
+            vec![PieceHexSpec {
+                offset: Hex::ZERO,
+                blueprint: Some(1),
+                rotation: i,
+            }]
+        })
+        .collect();
+    game.app
+        .world
+        .insert_resource(LotQueue::fixed([lot.clone()]));
+
+    use_up_lot(&mut game);
+
+    assert_eq!(lot_hexes(&mut game), spec_hexes(&game, &lot));
+    assert!(game.app.world.resource::<LotQueue>().peek().is_none());
+}
@@ -0,0 +1,315 @@
+//! Headless harness that drives the game loop without a window or a GPU.
+
+mod audio;
+mod daily;
+mod difficulty;
+mod game_loop;
+mod game_mode;
+mod hex_cursor;
+mod high_scores;
+mod level;
+mod lot_queue;
+mod pause;
+mod pieces;
+mod replay;
+mod scoring;
+mod settings;
+mod stash;
+
+use crate::{
+    animation::AnimationPlugin,
+    audio::GameAudioPlugin,
+    board::BoardLayout,
+    connectivity::{ConnectivityPlugin, HouseConnectivity},
+    cooldown::CooldownPlugin,
+    daily::DailyPlugin,
+    difficulty::{DifficultyConfig, DifficultyPlugin},
+    ecs::EcsPlugin,
+    game_mode::{GameMode, GameModePlugin},
+    game_over::GameOverPlugin,
+    hex_cursor::HexCursorPlugin,
+    high_scores::HighScoresPlugin,
+    history::{HistoryPlugin, RedoRequest, UndoRequest},
+    input::GameAction,
+    level::{LevelAsset, LevelPlugin},
+    loading::{AudioAssets, ConfigAssets, FontAssets, LevelAssets, MainCam, TextureAssets},
+    lot_queue::LotQueuePlugin,
+    map::{MapPlugin, WorldLayout, WorldMap},
+    map_completion::{CompletedMap, MapCompletionPlugin},
+    mouse::CursorPosition,
+    pause::PausePlugin,
+    piece::{Piece, PiecePlugin, PlacePieceRequest, RotatePieceRequest},
+    replay::ReplayPlugin,
+    reset::{RegisteredSystems, ResetPlugin},
+    score::{GameTimer, Level, Score, ScorePlugin},
+    settings::SettingsPlugin,
+    stash::{StashPieceRequest, StashPlugin, Stashed},
+    storage::{MemoryStore, Storage},
+    GameState,
+};
+use bevy::{
+    asset::{AssetApp, AssetPlugin},
+    prelude::*,
+    time::TimeUpdateStrategy,
+    window::WindowFocused,
+};
+use bevy_mod_picking::prelude::*;
+use bevy_trauma_shake::TraumaPlugin;
+use hexx::Hex;
+use leafwing_input_manager::prelude::*;
+use std::time::Duration;
+
+/// Duration of a single frame.
+pub const FRAME: Duration = Duration::from_millis(16);
+
+/// Parses a level file of the assets folder.
+pub fn load_level(path: &str) -> LevelAsset {
+    LevelAsset::from_ron(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Parses the difficulty config of the assets folder.
+pub fn load_difficulty() -> DifficultyConfig {
+    DifficultyConfig::from_ron(
+        &std::fs::read_to_string("assets/config/default.difficulty.ron").unwrap(),
+    )
+    .unwrap()
+}
+
+pub struct TestGame {
+    pub app: App,
+}
+
+impl TestGame {
+    /// Builds the game logic plugins with stubbed assets.
+    pub fn new() -> Self {
+        let mut app = App::new();
+
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            // there's no audio device, the sounds are only spawned
+            .init_asset::<AudioSource>()
+            .init_asset::<Pitch>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .add_state::<GameState>()
+            .add_event::<Pointer<Drag>>()
+            .add_event::<Pointer<DragEnd>>()
+            .add_event::<Pointer<Over>>()
+            .add_event::<Pointer<Out>>()
+            .add_event::<WindowFocused>()
+            .init_resource::<ActionState<GameAction>>()
+            .init_resource::<CursorPosition>()
+            .insert_resource(TextureAssets {
+                bevy: Handle::default(),
+                github: Handle::default(),
+                tiles: Handle::default(),
+            })
+            .insert_resource(FontAssets {
+                main: Handle::default(),
+            })
+            .insert_resource(AudioAssets {
+                music: Handle::default(),
+            })
+            .add_plugins((
+                MapPlugin,
+                PiecePlugin,
+                AnimationPlugin,
+                CooldownPlugin,
+                ResetPlugin,
+                MapCompletionPlugin,
+                ScorePlugin,
+                EcsPlugin,
+                TraumaPlugin,
+                GameOverPlugin,
+                HistoryPlugin,
+                ConnectivityPlugin,
+                HighScoresPlugin,
+                GameModePlugin,
+                LevelPlugin,
+            ))
+            .add_plugins((
+                DifficultyPlugin,
+                StashPlugin,
+                LotQueuePlugin,
+                ReplayPlugin,
+                DailyPlugin,
+                PausePlugin,
+                SettingsPlugin,
+                GameAudioPlugin,
+                HexCursorPlugin,
+            ))
+            .insert_resource(Storage(Box::<MemoryStore>::default()));
+
+        let puzzles = ["puzzle_1", "puzzle_2"]
+            .map(|name| {
+                let level = load_level(&format!("assets/levels/{name}.level.ron"));
+                app.world.resource_mut::<Assets<LevelAsset>>().add(level)
+            })
+            .to_vec();
+        app.insert_resource(LevelAssets { puzzles });
+
+        let difficulty = app
+            .world
+            .resource_mut::<Assets<DifficultyConfig>>()
+            .add(load_difficulty());
+        app.insert_resource(ConfigAssets { difficulty });
+
+        app.world.spawn((
+            OrthographicProjection::default(),
+            Transform::default(),
+            MainCam,
+        ));
+
+        Self { app }
+    }
+
+    /// Enters the game and runs it until the first lot has been offered.
+    pub fn start() -> Self {
+        Self::start_in(GameMode::default())
+    }
+
+    pub fn start_in(mode: GameMode) -> Self {
+        let mut game = Self::new();
+        game.app.world.insert_resource(mode);
+        game.set_state(GameState::Game);
+        game.update_until(|game| game.lot().len() == 3);
+
+        game
+    }
+
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    pub fn update_until(&mut self, mut condition: impl FnMut(&mut Self) -> bool) {
+        for _ in 0..1000 {
+            if condition(self) {
+                return;
+            }
+
+            self.update();
+        }
+
+        panic!("Condition not met in 1000 frames");
+    }
+
+    /// Advances the game time by `secs`.
+    pub fn advance(&mut self, secs: f32) {
+        let frames = (secs / FRAME.as_secs_f32()).ceil() as usize;
+
+        for _ in 0..frames {
+            self.update();
+        }
+    }
+
+    pub fn set_state(&mut self, state: GameState) {
+        self.app
+            .world
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        self.update();
+    }
+
+    pub fn state(&self) -> GameState {
+        self.app.world.resource::<State<GameState>>().get().clone()
+    }
+
+    /// Replaces the current board with `board` (the offered lot is kept).
+    pub fn set_board(&mut self, board: BoardLayout) {
+        self.app.world.insert_resource(board);
+        self.update();
+    }
+
+    /// Pieces of the offered lot ordered from the bottom one to the top one.
+    pub fn lot(&mut self) -> Vec<Entity> {
+        let mut pieces: Vec<_> = self
+            .app
+            .world
+            .query_filtered::<(Entity, &Transform), (With<Piece>, Without<Stashed>)>()
+            .iter(&self.app.world)
+            .map(|(e, t)| (e, t.translation.y))
+            .collect();
+        pieces.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        pieces.into_iter().map(|(e, _)| e).collect()
+    }
+
+    pub fn piece(&self, piece_e: Entity) -> &Piece {
+        self.app.world.get::<Piece>(piece_e).unwrap()
+    }
+
+    /// Rotates the `index`-th lot piece around its origin and waits for the rotation to finish.
+    pub fn rotate_piece(&mut self, index: usize, clockwise: bool) {
+        let piece = self.lot()[index];
+        self.app.world.send_event(RotatePieceRequest {
+            piece,
+            pivot: Hex::ZERO,
+            clockwise,
+        });
+        self.advance(0.35);
+    }
+
+    /// Rotates the `index`-th lot piece clockwise `rotation` times and places it with its origin at `hex`.
+    pub fn place_piece(&mut self, index: usize, hex: Hex, rotation: u8) {
+        for _ in 0..rotation {
+            self.rotate_piece(index, true);
+        }
+
+        let piece = self.lot()[index];
+        self.app.world.send_event(PlacePieceRequest { piece, hex });
+        self.update();
+    }
+
+    /// Parks the `index`-th lot piece in the hold slot.
+    pub fn stash_piece(&mut self, index: usize) {
+        let piece = self.lot()[index];
+        self.app.world.send_event(StashPieceRequest { piece });
+        self.update();
+    }
+
+    pub fn undo(&mut self) {
+        self.app.world.send_event(UndoRequest);
+        self.update();
+    }
+
+    pub fn redo(&mut self) {
+        self.app.world.send_event(RedoRequest);
+        // the placement request it sends can be handled a frame later
+        self.update();
+        self.update();
+    }
+
+    pub fn skip(&mut self) {
+        let skip = self.app.world.resource::<RegisteredSystems>().skip_board;
+        self.app.world.run_system(skip).unwrap();
+        self.update();
+    }
+
+    pub fn score(&self) -> u32 {
+        self.app.world.resource::<Score>().0
+    }
+
+    pub fn level(&self) -> u32 {
+        self.app.world.resource::<Level>().0
+    }
+
+    pub fn remaining_secs(&self) -> f32 {
+        self.app
+            .world
+            .resource::<GameTimer>()
+            .remaining()
+            .as_secs_f32()
+    }
+
+    pub fn map(&self) -> &WorldMap {
+        self.app.world.resource::<WorldMap>()
+    }
+
+    pub fn connectivity(&self) -> &HouseConnectivity {
+        self.app.world.resource::<HouseConnectivity>()
+    }
+
+    pub fn completed_map(&self) -> Option<&CompletedMap> {
+        self.app.world.get_resource::<CompletedMap>()
+    }
+}
@@ -0,0 +1,128 @@
+use super::{
+    game_loop::{completable_board, free_hex},
+    TestGame,
+};
+use crate::{
+    game_mode::RunClock,
+    high_scores::HighScores,
+    pause::{AbandonedRun, PauseState},
+    piece::{Piece, PlacePieceRequest},
+    replay::LastReplay,
+    reset::RegisteredSystems,
+    GameState,
+};
+use bevy::{prelude::*, window::WindowFocused};
+
+fn pause_state(game: &TestGame) -> PauseState {
+    game.app.world.resource::<State<PauseState>>().get().clone()
+}
+
+fn lose_focus(game: &mut TestGame) {
+    game.app.world.send_event(WindowFocused {
+        window: Entity::PLACEHOLDER,
+        focused: false,
+    });
+    game.update();
+    game.update();
+}
+
+fn resume(game: &mut TestGame) {
+    let resume = game.app.world.resource::<RegisteredSystems>().resume;
+    game.app.world.run_system(resume).unwrap();
+    game.update();
+}
+
+#[test]
+fn losing_focus_pauses_the_run() {
+    let mut game = TestGame::start();
+    lose_focus(&mut game);
+    assert_eq!(pause_state(&game), PauseState::Paused);
+    let remaining = game.remaining_secs();
+    let clock = game.app.world.resource::<RunClock>().elapsed_secs();
+
+    game.advance(10.);
+
+    assert_eq!(game.state(), GameState::Game);
+    assert_eq!(game.remaining_secs(), remaining);
+    assert_eq!(game.app.world.resource::<RunClock>().elapsed_secs(), clock);
+
+    resume(&mut game);
+    game.advance(1.);
+
+    assert_eq!(pause_state(&game), PauseState::Running);
+    assert!(game.remaining_secs() < remaining - 0.9);
+}
+
+#[test]
+fn paused_run_ignores_piece_input() {
+    let mut game = TestGame::start();
+    let piece_e = game.lot()[0];
+    let hex = free_hex(&game, piece_e);
+    lose_focus(&mut game);
+
+    game.app.world.send_event(PlacePieceRequest {
+        piece: piece_e,
+        hex,
+    });
+    game.update();
+
+    assert!(game.app.world.get::<Piece>(piece_e).is_some());
+}
+
+#[test]
+fn restarting_starts_a_fresh_run() {
+    let mut game = TestGame::start();
+    let piece_e = game.lot()[0];
+    game.place_piece(0, free_hex(&game, piece_e), 0);
+    game.advance(5.);
+    lose_focus(&mut game);
+
+    let restart = game.app.world.resource::<RegisteredSystems>().restart;
+    game.app.world.run_system(restart).unwrap();
+    game.update_until(|game| game.lot().len() == 3);
+
+    assert_eq!(game.state(), GameState::Game);
+    assert_eq!(pause_state(&game), PauseState::Running);
+    assert!(game.remaining_secs() > 149.);
+    assert!(game.app.world.get_entity(piece_e).is_none());
+    assert_eq!(game.map().placed_pieces(), 0);
+
+    // neither the restarted run nor one quit for the tutorial is recorded
+    assert!(game.app.world.get_resource::<AbandonedRun>().is_none());
+    lose_focus(&mut game);
+    let quit = game.app.world.resource::<RegisteredSystems>().quit;
+    game.app.world.run_system(quit).unwrap();
+    game.update();
+
+    assert_eq!(game.state(), GameState::Tutorial);
+    assert!(game.app.world.resource::<HighScores>().entries.is_empty());
+    assert!(game.app.world.resource::<LastReplay>().0.is_none());
+}
+
+#[test]
+fn restarting_mid_completion_drops_the_completed_hive() {
+    let mut game = TestGame::start();
+    let (index, origin, board) = completable_board(&game);
+    game.set_board(board);
+    game.place_piece(index, origin, 0);
+    game.update();
+    assert!(game.completed_map().is_some());
+
+    // the hive is being scored, so the run can't be paused
+    let systems = game.app.world.resource::<RegisteredSystems>();
+    let (pause, restart) = (systems.pause, systems.restart);
+    game.app.world.run_system(pause).unwrap();
+    game.update();
+    assert_eq!(pause_state(&game), PauseState::Running);
+
+    game.app.world.run_system(restart).unwrap();
+    game.update_until(|game| game.lot().len() == 3);
+    let piece_e = game.lot()[0];
+    game.place_piece(0, free_hex(&game, piece_e), 0);
+    game.advance(5.);
+
+    assert_eq!(game.score(), 0);
+    assert_eq!(game.level(), 0);
+    assert!(game.completed_map().is_none());
+    assert_eq!(game.map().placed_pieces(), 1);
+}
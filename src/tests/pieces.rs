@@ -0,0 +1,190 @@
+use super::{load_difficulty, lot_queue::use_up_lot, TestGame};
+use crate::{
+    board::BoardLayout,
+    difficulty::DifficultyConfig,
+    lot_queue::{LotQueue, LotSpec},
+    map::{WorldLayout, HEX_WIDTH},
+    piece::{
+        generate_piece, HexBlueprints, InitialPosition, PieceHexSpec, PlacePieceRequest,
+        RotatePieceRequest,
+    },
+};
+use bevy::prelude::*;
+use hexx::Hex;
+use rand::{rngs::StdRng, SeedableRng};
+
+fn triples_only(config: &mut DifficultyConfig) {
+    for tier in config.tiers.iter_mut() {
+        tier.piece_size_weights = vec![0, 0, 1];
+    }
+}
+
+/// Starts a game offering a lot of three hex pieces.
+fn start_with_triples() -> (TestGame, Entity) {
+    let mut config = load_difficulty();
+    triples_only(&mut config);
+    let blueprints = HexBlueprints::new(&config).unwrap();
+    let mut rng = StdRng::seed_from_u64(7);
+    // most generated pieces end early on an empty hex, so the lot only keeps the full ones
+    let lot: LotSpec = std::iter::repeat_with(|| generate_piece(&blueprints, 0, &mut rng))
+        .filter(|piece| piece.len() == 3)
+        .take(3)
+        .collect();
+
+    let mut game = TestGame::start();
+    game.app.world.insert_resource(LotQueue::fixed([lot]));
+    use_up_lot(&mut game);
+    let piece_e = game.lot()[0];
+
+    (game, piece_e)
+}
+
+fn side_connected(blueprints: &HexBlueprints, hex: &PieceHexSpec, side: usize) -> bool {
+    blueprints
+        .connections(hex)
+        .map_or(false, |connected_sides| connected_sides[side])
+}
+
+#[test]
+fn generated_triples_are_lines_or_triangles() {
+    let mut config = load_difficulty();
+    triples_only(&mut config);
+    let blueprints = HexBlueprints::new(&config).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut triples = 0;
+
+    for _ in 0..200 {
+        let piece = generate_piece(&blueprints, 0, &mut rng);
+
+        if piece.len() < 3 {
+            continue;
+        }
+
+        triples += 1;
+        let mut adjacent_pairs = 0;
+
+        for (i, a) in piece.iter().enumerate() {
+            assert!(a.offset.ulength() <= 1, "Piece reaches too far: {piece:?}");
+
+            for b in piece.iter().skip(i + 1) {
+                let Some(side) =
+                    (0..6).find(|side| a.offset + Hex::new(1, -1).rotate_cw(*side) == b.offset)
+                else {
+                    continue;
+                };
+
+                adjacent_pairs += 1;
+                assert_eq!(
+                    side_connected(&blueprints, a, side as usize),
+                    side_connected(&blueprints, b, (side as usize + 3) % 6),
+                    "Edge doesn't match: {piece:?}"
+                );
+            }
+        }
+
+        let is_line = piece.iter().any(|middle| {
+            middle.offset == Hex::ZERO
+                && piece.iter().any(|end| {
+                    end.offset != Hex::ZERO
+                        && piece.iter().any(|o| o.offset == Hex::ZERO - end.offset)
+                })
+        });
+        assert!(
+            adjacent_pairs == 3 || (adjacent_pairs == 2 && is_line),
+            "Not a line or a triangle: {piece:?}"
+        );
+    }
+
+    assert!(triples > 0);
+}
+
+#[test]
+fn triple_rotates_around_hovered_hex() {
+    let (mut game, piece_e) = start_with_triples();
+    let hexes_before: Vec<_> = game
+        .piece(piece_e)
+        .hexes()
+        .map(|(hex, data)| (*hex, data.entity))
+        .collect();
+    // the hex furthest from the origin, so the whole piece moves around it
+    let pivot = hexes_before
+        .iter()
+        .map(|(hex, _)| *hex)
+        .max_by_key(|hex| hex.ulength())
+        .unwrap();
+
+    game.app.world.send_event(RotatePieceRequest {
+        piece: piece_e,
+        pivot,
+        clockwise: true,
+    });
+    game.advance(0.35);
+
+    let piece = game.piece(piece_e);
+    for (hex, entity) in hexes_before {
+        let (rotated_hex, _) = piece
+            .hexes()
+            .find(|(_, data)| data.entity == entity)
+            .unwrap();
+
+        assert_eq!(*rotated_hex, hex.cw_around(pivot));
+    }
+}
+
+#[test]
+fn triple_needs_room_for_every_hex() {
+    let (mut game, piece_e) = start_with_triples();
+    let mut playable: Vec<_> = game.piece(piece_e).hexes().map(|(hex, _)| *hex).collect();
+    game.set_board(BoardLayout {
+        map_radius: 0,
+        playable: playable.clone(),
+        houses: vec![Hex::new(5, 0), Hex::new(-5, 0)],
+        blocked: Vec::new(),
+        routes: Vec::new(),
+    });
+
+    assert!(game.piece(piece_e).fits(game.map(), Hex::ZERO));
+
+    playable.pop();
+    game.set_board(BoardLayout {
+        map_radius: 0,
+        playable,
+        houses: vec![Hex::new(5, 0), Hex::new(-5, 0)],
+        blocked: Vec::new(),
+        routes: Vec::new(),
+    });
+    game.app.world.send_event(PlacePieceRequest {
+        piece: piece_e,
+        hex: Hex::ZERO,
+    });
+    game.update();
+
+    assert!(!game.piece(piece_e).fits(game.map(), Hex::ZERO));
+    assert!(game.lot().contains(&piece_e));
+}
+
+#[test]
+fn lot_triples_dont_overlap() {
+    let (mut game, _) = start_with_triples();
+    let lot = game.lot();
+    let layout = game.app.world.resource::<WorldLayout>();
+    let pieces: Vec<Vec<Vec2>> = lot
+        .into_iter()
+        .map(|e| {
+            let pos = game.app.world.get::<InitialPosition>(e).unwrap().truncate();
+
+            game.piece(e)
+                .hexes()
+                .map(|(hex, _)| pos + layout.hex_to_world_pos(*hex))
+                .collect()
+        })
+        .collect();
+
+    for (i, a) in pieces.iter().enumerate() {
+        for b in pieces.iter().skip(i + 1) {
+            for (a, b) in a.iter().flat_map(|a| b.iter().map(move |b| (a, b))) {
+                assert!(a.distance(*b) > HEX_WIDTH - 0.01, "Lot pieces overlap");
+            }
+        }
+    }
+}
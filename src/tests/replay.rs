@@ -0,0 +1,101 @@
+use super::{game_loop::free_hex, TestGame};
+use crate::{
+    game_mode::GameMode,
+    replay::{LastReplay, Replay, ReplayAction, ReplayPlayback},
+    GameState,
+};
+use hexx::Hex;
+
+/// Ends the run and returns its replay along with the score and level it ended with.
+fn finish_run(game: &mut TestGame) -> (Replay, u32, u32) {
+    let outcome = (game.score(), game.level());
+    game.set_state(GameState::GameOver);
+    let replay = game.app.world.resource::<LastReplay>().0.clone().unwrap();
+
+    (replay, outcome.0, outcome.1)
+}
+
+/// Plays `replay` back in a fresh game and returns the replay recorded by the playback
+/// along with the score and level it ended with.
+fn play_back(replay: &Replay) -> (Replay, u32, u32) {
+    let mut game = TestGame::new();
+    game.app.world.insert_resource(replay.mode);
+    game.app
+        .world
+        .insert_resource(ReplayPlayback::new(replay.clone()));
+    game.set_state(GameState::Game);
+
+    // the level gets reset once the run is over
+    let mut outcome = (0, 0);
+    game.update_until(|game| {
+        if game.state() == GameState::Game {
+            outcome = (game.score(), game.level());
+        }
+
+        game.state() == GameState::GameOver
+    });
+    let played = game.app.world.resource::<LastReplay>().0.clone().unwrap();
+
+    (played, outcome.0, outcome.1)
+}
+
+#[test]
+fn playback_reproduces_timed_run() {
+    let mut game = TestGame::start();
+    game.rotate_piece(1, true);
+    let piece_e = game.lot()[1];
+    game.place_piece(1, free_hex(&game, piece_e), 0);
+    game.undo();
+    game.stash_piece(0);
+    game.skip();
+    game.update_until(|game| game.lot().len() == 3);
+    let piece_e = game.lot()[2];
+    game.place_piece(2, free_hex(&game, piece_e), 0);
+    game.advance(1.);
+
+    let (replay, score, level) = finish_run(&mut game);
+    let actions = |replay: &Replay| -> Vec<_> {
+        replay
+            .events
+            .iter()
+            .filter(|ev| !matches!(ev.action, ReplayAction::Offered(_)))
+            .cloned()
+            .collect()
+    };
+    assert_eq!(actions(&replay).len(), 6);
+    assert_eq!(replay.offered().count(), 2);
+
+    let (played, played_score, played_level) = play_back(&replay);
+
+    assert!(replay.matches(&played));
+    assert_eq!(actions(&played), actions(&replay));
+    assert_eq!((played_score, played_level), (score, level));
+}
+
+#[test]
+fn playback_reproduces_solved_puzzle() {
+    let mut game = TestGame::start_in(GameMode::Puzzle);
+    game.place_piece(0, Hex::new(-1, 0), 0);
+    game.place_piece(0, Hex::new(1, 0), 2);
+    game.advance(5.);
+
+    let (replay, score, level) = finish_run(&mut game);
+    assert_eq!(level, 1);
+
+    let (played, played_score, played_level) = play_back(&replay);
+
+    assert!(replay.matches(&played));
+    assert_eq!((played_score, played_level), (score, level));
+}
+
+#[test]
+fn replay_survives_serialization() {
+    let mut game = TestGame::start();
+    game.rotate_piece(0, false);
+    game.stash_piece(0);
+
+    let (replay, ..) = finish_run(&mut game);
+    let data = ron::to_string(&replay).unwrap();
+
+    assert_eq!(ron::from_str::<Replay>(&data).unwrap(), replay);
+}
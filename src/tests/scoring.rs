@@ -0,0 +1,144 @@
+use crate::{
+    board::BoardLayout,
+    map::WorldMap,
+    piece::PieceHexData,
+    scoring::{
+        evaluate, ScoreBreakdown, ScoreItem, DEAD_ENDS, EMPTY_HEXES, FEW_PIECES, HOUSES, LOOPS,
+        SHORT_ROUTES, SPEED, UNUSED_HEXES,
+    },
+};
+use bevy::{prelude::*, utils::HashMap};
+use hexx::Hex;
+
+fn side(from: Hex, to: Hex) -> usize {
+    (0..6)
+        .find(|side| from + Hex::new(1, -1).rotate_cw(*side as u32) == to)
+        .expect("Route hexes should be neighbours")
+}
+
+fn place_single(map: &mut WorldMap, hex: Hex, sides: [bool; 6]) {
+    let piece_hexes: HashMap<_, _> = [(
+        Hex::ZERO,
+        PieceHexData::new(Entity::PLACEHOLDER, Some(sides)),
+    )]
+    .into_iter()
+    .collect();
+    map.place_piece(hex, &piece_hexes);
+}
+
+/// Builds a map between houses at (-2, 0) and (2, 0) with every route hex placed as a single hex piece.
+/// The routes start and end with a house.
+fn fixture(playable: &[(i32, i32)], routes: &[&[(i32, i32)]]) -> WorldMap {
+    let board = BoardLayout {
+        map_radius: 2,
+        playable: playable.iter().map(|(x, y)| Hex::new(*x, *y)).collect(),
+        houses: vec![Hex::new(-2, 0), Hex::new(2, 0)],
+        blocked: Vec::new(),
+        routes: Vec::new(),
+    };
+    let mut map = WorldMap::new(&board, |_| Entity::PLACEHOLDER);
+
+    for route in routes {
+        let route: Vec<_> = route.iter().map(|(x, y)| Hex::new(*x, *y)).collect();
+
+        for hexes in route.windows(3) {
+            let mut sides = [false; 6];
+            sides[side(hexes[1], hexes[0])] = true;
+            sides[side(hexes[1], hexes[2])] = true;
+            place_single(&mut map, hexes[1], sides);
+        }
+    }
+
+    map.check_consistency().unwrap();
+    map
+}
+
+#[test]
+fn straight_route_scores_every_bonus() {
+    let mut map = fixture(
+        &[(-1, 0), (0, 0), (1, 0), (0, 1), (0, -1), (-1, 1), (1, -1)],
+        &[&[(-2, 0), (-1, 0), (0, 0), (1, 0), (2, 0)]],
+    );
+    let completed_map = map.get_completed_routes().unwrap();
+
+    let breakdown = evaluate(&map, &completed_map, 0.);
+
+    assert_eq!(breakdown.points(HOUSES), 20);
+    assert_eq!(breakdown.points(DEAD_ENDS), 0);
+    assert_eq!(breakdown.points(SHORT_ROUTES), 5);
+    assert_eq!(breakdown.points(FEW_PIECES), 1);
+    assert_eq!(breakdown.points(UNUSED_HEXES), 0);
+    assert_eq!(breakdown.points(LOOPS), 0);
+    assert_eq!(breakdown.points(EMPTY_HEXES), 1);
+    assert_eq!(breakdown.points(SPEED), 10);
+    assert_eq!(breakdown.total(), 37);
+}
+
+#[test]
+fn detours_and_unused_hexes_cost_points() {
+    let mut map = fixture(
+        &[(-1, 0), (0, -1), (1, -1), (2, -1), (0, 0), (0, 1)],
+        &[&[(-2, 0), (-1, 0), (0, -1), (1, -1), (2, -1), (2, 0)]],
+    );
+
+    // a lone hex leading nowhere
+    let mut sides = [false; 6];
+    sides[side(Hex::new(0, 1), Hex::new(1, 1))] = true;
+    place_single(&mut map, Hex::new(0, 1), sides);
+
+    let completed_map = map.get_completed_routes().unwrap();
+    let breakdown = evaluate(&map, &completed_map, 120.);
+
+    assert_eq!(breakdown.points(SHORT_ROUTES), 4);
+    assert_eq!(breakdown.points(DEAD_ENDS), -1);
+    assert_eq!(breakdown.points(UNUSED_HEXES), -1);
+    assert_eq!(breakdown.points(LOOPS), 0);
+    assert_eq!(breakdown.points(SPEED), 0);
+}
+
+#[test]
+fn parallel_routes_form_a_loop() {
+    let mut map = fixture(
+        &[(-1, 0), (0, 0), (1, 0), (-1, -1), (0, -1), (1, -1), (2, -1)],
+        &[
+            &[(-2, 0), (-1, 0), (0, 0), (1, 0), (2, 0)],
+            &[(-2, 0), (-1, -1), (0, -1), (1, -1), (2, -1), (2, 0)],
+        ],
+    );
+    let completed_map = map.get_completed_routes().unwrap();
+
+    let breakdown = evaluate(&map, &completed_map, 0.);
+
+    assert_eq!(breakdown.points(LOOPS), 2);
+    assert_eq!(breakdown.points(DEAD_ENDS), 0);
+}
+
+#[test]
+fn streak_multiplies_gains_but_not_dead_ends() {
+    let breakdown = ScoreBreakdown {
+        items: vec![
+            ScoreItem {
+                label: HOUSES,
+                points: 20,
+            },
+            ScoreItem {
+                label: DEAD_ENDS,
+                points: -2,
+            },
+        ],
+        multiplier: 1.5,
+    };
+
+    assert_eq!(breakdown.total(), 18);
+    assert_eq!(breakdown.route_points(), 30);
+    assert_eq!(breakdown.streak_total(), 28);
+
+    let losing = ScoreBreakdown {
+        items: vec![ScoreItem {
+            label: UNUSED_HEXES,
+            points: -3,
+        }],
+        multiplier: 2.,
+    };
+    assert_eq!(losing.streak_total(), -3);
+}
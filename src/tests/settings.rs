@@ -0,0 +1,97 @@
+use super::TestGame;
+use crate::{
+    input::{GameAction, KeyBindings},
+    pause::PauseState,
+    reset::RegisteredSystems,
+    score::HoldText,
+    settings::Settings,
+    storage::{KeyValueStore, MemoryStore, Storage},
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+#[test]
+fn settings_round_trip_through_store() {
+    let mut store = MemoryStore::default();
+    let settings = Settings {
+        sfx_volume: 0.3,
+        shake: 0.,
+        colorblind: true,
+        ..default()
+    };
+
+    settings.save(&mut store);
+
+    assert_eq!(Settings::load(&store), settings);
+    assert_eq!(Settings::load(&MemoryStore::default()), Settings::default());
+
+    // settings saved by an older version keep the defaults of the new options
+    store.save("settings.ron", "(shake: 0.5)");
+    assert_eq!(
+        Settings::load(&store),
+        Settings {
+            shake: 0.5,
+            ..default()
+        }
+    );
+}
+
+#[test]
+fn rebinding_swaps_keys_and_updates_input_map() {
+    let mut bindings = KeyBindings::default();
+    bindings.rebind(GameAction::Undo, KeyCode::Q);
+    bindings.rebind(GameAction::Stash, KeyCode::F);
+
+    assert_eq!(bindings.key(GameAction::Undo), Some(KeyCode::Q));
+    assert_eq!(bindings.key(GameAction::RotateCcw), Some(KeyCode::Z));
+
+    let mut game = TestGame::start();
+    game.app.world.resource_mut::<Settings>().bindings = bindings.clone();
+    game.update();
+
+    assert_eq!(
+        *game.app.world.resource::<InputMap<GameAction>>(),
+        bindings.input_map()
+    );
+    let stored = Settings::load(game.app.world.resource::<Storage>().0.as_ref());
+    assert_eq!(stored.bindings, bindings);
+
+    // the HUD names the rebound key
+    let hold_text = game
+        .app
+        .world
+        .query_filtered::<&Text, With<HoldText>>()
+        .single(&game.app.world)
+        .sections[0]
+        .value
+        .clone();
+    assert_eq!(hold_text, "HOLD [F]");
+}
+
+#[test]
+fn paused_settings_return_to_pause_menu() {
+    let mut game = TestGame::start();
+    let systems = game.app.world.resource::<RegisteredSystems>();
+    let (pause, pause_settings) = (systems.pause, systems.pause_settings);
+
+    game.app.world.run_system(pause).unwrap();
+    game.update();
+    game.app.world.run_system(pause_settings).unwrap();
+    game.update();
+
+    assert_eq!(
+        *game.app.world.resource::<State<PauseState>>().get(),
+        PauseState::Settings
+    );
+    let remaining = game.remaining_secs();
+    game.advance(2.);
+    assert_eq!(game.remaining_secs(), remaining);
+
+    game.app.world.run_system(pause).unwrap();
+    game.update();
+
+    assert_eq!(
+        *game.app.world.resource::<State<PauseState>>().get(),
+        PauseState::Paused
+    );
+}
@@ -0,0 +1,92 @@
+use super::{game_loop::free_hex, TestGame};
+use crate::{
+    piece::{Piece, PlacePieceRequest},
+    stash::Stashed,
+};
+use bevy::prelude::*;
+
+fn stashed(game: &mut TestGame) -> Option<Entity> {
+    game.app
+        .world
+        .query_filtered::<Entity, (With<Piece>, With<Stashed>)>()
+        .iter(&game.app.world)
+        .next()
+}
+
+#[test]
+fn stashing_parks_piece_outside_lot() {
+    let mut game = TestGame::start();
+    let piece_e = game.lot()[0];
+    let remaining = game.remaining_secs();
+
+    game.stash_piece(0);
+
+    assert_eq!(stashed(&mut game), Some(piece_e));
+    assert_eq!(game.lot().len(), 2);
+    // parking a piece in the empty slot is free
+    assert!(game.remaining_secs() > remaining - 0.1);
+
+    // the last lot piece gets discarded, but the stashed one stays
+    let lot_piece = game.lot()[0];
+    let hex = free_hex(&game, lot_piece);
+    game.app.world.send_event(PlacePieceRequest {
+        piece: lot_piece,
+        hex,
+    });
+    game.update_until(|game| game.lot().len() == 3);
+
+    assert_eq!(stashed(&mut game), Some(piece_e));
+}
+
+#[test]
+fn swapping_stashed_piece_costs_time() {
+    let mut game = TestGame::start();
+    let first_e = game.lot()[0];
+    game.stash_piece(0);
+    let second_e = game.lot()[1];
+    let remaining = game.remaining_secs();
+
+    game.stash_piece(1);
+    // the timer event can be read a frame later
+    game.update();
+
+    assert_eq!(stashed(&mut game), Some(second_e));
+    assert!(game.lot().contains(&first_e));
+    assert_eq!(game.lot().len(), 2);
+    assert!(game.remaining_secs() < remaining - 4.9);
+}
+
+#[test]
+fn stashed_piece_is_kept_for_next_board() {
+    let mut game = TestGame::start();
+    game.stash_piece(0);
+    let piece_e = stashed(&mut game).unwrap();
+
+    game.skip();
+    game.update_until(|game| game.lot().len() == 3);
+
+    assert_eq!(stashed(&mut game), Some(piece_e));
+}
+
+#[test]
+fn undoing_stashed_placement_parks_piece_again() {
+    let mut game = TestGame::start();
+    game.stash_piece(0);
+    let piece_e = stashed(&mut game).unwrap();
+    let hex = free_hex(&game, piece_e);
+
+    game.app.world.send_event(PlacePieceRequest {
+        piece: piece_e,
+        hex,
+    });
+    game.update();
+
+    assert_eq!(stashed(&mut game), None);
+    // placing the stashed piece doesn't use up the lot
+    assert_eq!(game.lot().len(), 2);
+
+    game.undo();
+    game.update();
+
+    assert_eq!(stashed(&mut game), Some(piece_e));
+}
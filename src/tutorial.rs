@@ -1,9 +1,11 @@
 use crate::{
     animation::{delay_tween, get_scale_tween},
+    game_mode::{spawn_mode_btn, GameMode},
     loading::FontAssets,
     menu::spawn_play_btn,
-    reset::{tween_reset, Resettable},
+    reset::{tween_reset, RegisteredSystems, Resettable},
     score::Score,
+    settings::spawn_settings_btn,
     GameState,
 };
 use bevy::prelude::*;
@@ -17,7 +19,12 @@ impl Plugin for TutorialPlugin {
     }
 }
 
-fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>) {
+fn setup_ui(
+    mut cmd: Commands,
+    fonts: Res<FontAssets>,
+    mode: Res<GameMode>,
+    systems: Res<RegisteredSystems>,
+) {
     cmd.spawn((NodeBundle {
         style: Style {
             flex_direction: FlexDirection::Column,
@@ -67,7 +74,7 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>) {
                         
 
                         After you are finished rating the game, feel free to roast me, I'm very much open to constructive feedback no matter how harsh.
-                        Because jams happen imagine hearing pleasant upbeat music and punchy SFX and while you are at it picture a nice interactive tutorail too.",
+                        Because jams happen picture a nice interactive tutorail too.",
                         TextStyle {
                             font_size: 30.,
                             font: fonts.main.clone(),
@@ -116,6 +123,8 @@ fn setup_ui(mut cmd: Commands, fonts: Res<FontAssets>) {
                 Resettable,
             ));
 
+            spawn_mode_btn(b, 1100, fonts.main.clone(), *mode, systems.cycle_mode);
             spawn_play_btn(b, 1200, fonts.main.clone());
+            spawn_settings_btn(b, 1300, fonts.main.clone());
         });
 }